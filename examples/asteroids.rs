@@ -0,0 +1,49 @@
+use endless::graphics::*;
+
+const ASTEROIDS: usize = 2000;
+
+// a belt of spinning rocks drawn as one instanced mesh
+struct Asteroids {
+    belt: Option<InstancedMeshId>,
+}
+
+impl Asteroids {
+    // place every rock on a band around the origin, spun by the elapsed time
+    fn instances(elapsed: f32) -> Vec<Instance> {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+
+        (0..ASTEROIDS).map(|i| {
+            let t = i as f32 / ASTEROIDS as f32;
+            let angle = t * 2.0 * std::f32::consts::PI + elapsed * 0.05;
+            let radius = 2.0 + (i as f32 * 0.618).fract() * 0.8;
+            let height = ((i as f32 * 1.618).fract() - 0.5) * 0.3;
+
+            let mut transform = rotation_about(elapsed + i as f32, origin, Vec3::new(0.3, 1.0, 0.2));
+            transform[3] = [radius * angle.cos(), height, radius * angle.sin(), 1.0];
+
+            let shade = 0.5 + (i as f32 * 0.37).fract() * 0.5;
+            Instance::new(transform, Color::new(shade, shade * 0.9, shade * 0.8, 1.0))
+        }).collect()
+    }
+}
+
+impl App for Asteroids {
+    fn init(&mut self, context: &mut Context) {
+        let rock = Sphere::Icosahedron.new(0.03, Position::new(0.0, 0.0, 0.0, 1.0));
+
+        self.belt = Some(context.graphics.add_instanced(rock.mesh().clone(), Self::instances(0.0)));
+        context.graphics.camera.set_position(Position::new(0.0, 1.5, 5.0, 1.0));
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        let Some(belt) = self.belt.and_then(|belt| context.graphics.instanced_mut(belt)) else { return };
+
+        belt.instances = Self::instances(context.time.elapsed);
+    }
+}
+
+fn main() {
+    let asteroids = Asteroids { belt: None };
+
+    let _ = pollster::block_on(run(asteroids));
+}
@@ -8,7 +8,7 @@ fn main() {
 
     let mut cube = Cube::new(origin, size);
 
-    cube.subdivide(6);
+    cube.subdivide(6).expect("cube subdivision fits");
     cube.dedup();
 
     let _ = pollster::block_on(run(cube));
@@ -0,0 +1,42 @@
+use endless::graphics::*;
+use endless::types::*;
+
+const BEACONS: usize = 50;
+const SPACING: f64 = 100.0;
+const SPEED: f64 = 200.0;
+
+// fly past a line of beacons an astronomical unit from the world origin, where f32 alone would jitter by kilometres
+struct DeepSpace {
+    start: WorldPosition,
+}
+
+impl App for DeepSpace {
+    fn init(&mut self, context: &mut Context) {
+        let graphics = &mut context.graphics;
+
+        // centre render space on the camera before placing anything near it
+        graphics.set_camera_world_position(self.start);
+
+        for i in 0..BEACONS {
+            let side = if i % 2 == 0 { -6.0 } else { 6.0 };
+            let world = self.start + Vec3::new(side, 0.0, -(i as f64 * SPACING) as f32);
+
+            let beacon = graphics.add_geometry(Cube::new(Position::new(0.0, 0.0, 0.0, 1.0), 2.0));
+            graphics.set_world_position(beacon, world);
+        }
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        // the camera keeps its world position in f64, the render origin is moved whenever it gets too far away
+        let travelled = (context.time.elapsed as f64 * SPEED) % (BEACONS as f64 * SPACING);
+        let camera = WorldPosition::new(self.start.x, self.start.y + 2.0, self.start.z + 20.0 - travelled);
+
+        context.graphics.set_camera_world_position(camera);
+    }
+}
+
+fn main() {
+    let deep_space = DeepSpace { start: WorldPosition::new(1.496e11, 0.0, 0.0) };
+
+    let _ = pollster::block_on(run(deep_space));
+}
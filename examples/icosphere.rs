@@ -1,4 +1,4 @@
-use endless::graphics::{Sphere, Spherical};
+use endless::graphics::Sphere;
 use endless::graphics::Position;
 use endless::graphics::run;
 
@@ -6,10 +6,9 @@ fn main() {
     let origin = Position::new(0.0, 0.0, 0.25, 1.0);
     let radius = 0.3;
     let sphere = Sphere::Icosahedron;
-    let sphere = sphere.new(radius, origin);
+    let mut sphere = sphere.new(radius, origin);
 
-    // sphere.subdivide(6);
-    // sphere.dedup();
+    sphere.subdivide(6).expect("sphere subdivision fits");
 
     let _ = pollster::block_on(run(sphere));
 }
\ No newline at end of file
@@ -4,7 +4,7 @@ use endless::graphics::Vertex;
 use endless::graphics::Color;
 use endless::graphics::Position;
 use endless::graphics::Normal;
-use endless::graphics::run;
+use endless::graphics::{run, App, Context, LineStyle, LineWidth, LineCap, LineJoin, Facing};
 
 fn _plot_line(line: Line, screen_width: u32, screen_height: u32) {
     let mut screen = vec![vec![' '; screen_width as usize]; screen_height as usize];
@@ -24,28 +24,35 @@ fn _plot_line(line: Line, screen_width: u32, screen_height: u32) {
     }
 }
 
+// draw a thick line across the screen, faced and sized in pixels for the camera as it is at startup
+struct Lines;
+
+impl App for Lines {
+    fn init(&mut self, context: &mut Context) {
+        let starting_vertex = Vertex::new(
+            Position::new(-0.8, -0.5, 0.0, 1.0),
+            Color::blue(),
+            Normal::new(0.0, 0.0, 1.0)
+        );
+
+        let ending_vertex = Vertex::new(
+            Position::new(0.8, 0.5, 0.0, 1.0),
+            Color::pink(),
+            Normal::new(0.0, 0.0, 1.0)
+        );
+
+        let line = Line::styled(
+            starting_vertex,
+            ending_vertex,
+            LineStyle::new(LineWidth::Pixels(20.0), LineCap::Round, LineJoin::Miter),
+            Facing::camera(&context.graphics.camera, context.graphics.size.height),
+            20
+        );
+
+        context.graphics.add_mesh(line.mesh, Position::new(0.0, 0.0, 0.0, 1.0));
+    }
+}
+
 pub fn main () {
-    const WINDOW_HEIGHT: u32 = 1200;
-    const WINDOW_WIDTH: u32 = 1600;
-
-    let starting_vertex = Vertex::new(
-        Position::new(100.0, 100.0, 0.0, 0.0), 
-        Color::blue(),
-        Normal::new(1.0, 0.0, 1.0)
-    );
-
-    let ending_vertex = Vertex::new(
-        Position::new(WINDOW_WIDTH as f32 - 100.0, WINDOW_HEIGHT as f32 - 100.0, 0.0, 0.0), 
-        Color::blue(),
-        Normal::new(0.0, 0.0, 1.0)
-    );
-
-    let line = Line::new(
-        starting_vertex,
-        ending_vertex,
-        20.0,        
-        20
-    );
-
-    let _ = pollster::block_on(run(line.mesh));
-}
\ No newline at end of file
+    let _ = pollster::block_on(run(Lines));
+}
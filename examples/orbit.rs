@@ -0,0 +1,41 @@
+use endless::graphics::*;
+
+// orbit the camera around a cube and a sphere
+struct Orbit {
+    angle: f32,
+    distance: f32,
+}
+
+impl App for Orbit {
+    fn init(&mut self, context: &mut Context) {
+        context.graphics.add_geometry(Cube::new(Position::new(-0.6, 0.0, 0.0, 1.0), 0.5));
+        context.graphics.add_geometry(Sphere::UVSphere.new(0.3, Position::new(0.6, 0.0, 0.0, 1.0)));
+    }
+
+    fn fixed_update(&mut self, context: &mut Context) {
+        self.angle += 0.5 * context.time.delta;
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        let camera = &mut context.graphics.camera;
+
+        // look back at the origin from a point on the orbit
+        camera.set_position(Position::new(self.distance * self.angle.sin(), 0.0, self.distance * self.angle.cos(), 1.0));
+        camera.yaw = -self.angle;
+    }
+
+    fn render(&mut self, context: &mut Context) {
+        let debug = &mut context.graphics.debug_draw;
+
+        // mark the world origin and the bounds of both shapes
+        debug.axes(Mat4::IDENTITY, 0.25);
+        debug.aabb(Position::new(-0.85, -0.25, -0.25, 1.0), Position::new(-0.35, 0.25, 0.25, 1.0), Color::yellow());
+        debug.sphere(Position::new(0.6, 0.0, 0.0, 1.0), 0.32, Color::cyan());
+    }
+}
+
+fn main() {
+    let orbit = Orbit { angle: 0.0, distance: 3.0 };
+
+    let _ = pollster::block_on(run(orbit));
+}
@@ -24,7 +24,7 @@ fn main() {
     let screen_width = 75;
     let screen_height = 50;
 
-    let ring = Ring::new(
+    let ring = Ring::ring(
         Position::new(screen_width as f32 / 2.0, screen_height as f32 / 2.0, 0.0, 0.0),
         17.0,
         7.0,
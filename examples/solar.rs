@@ -0,0 +1,51 @@
+use endless::graphics::*;
+
+// a planet circling a sun with a moon circling the planet, each carried along by its parent
+struct Solar {
+    sun: Option<ObjectId>,
+    planet: Option<ObjectId>,
+}
+
+impl App for Solar {
+    fn init(&mut self, context: &mut Context) {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let graphics = &mut context.graphics;
+
+        let sun = graphics.add_geometry(Sphere::Icosahedron.new(0.4, origin));
+        let planet = graphics.add_geometry(Sphere::UVSphere.new(0.15, origin));
+        let moon = graphics.add_geometry(Cube::new(origin, 0.08));
+
+        // children are placed relative to their parents
+        graphics.set_parent(planet, Some(sun));
+        graphics.set_parent(moon, Some(planet));
+        if let Some(transform) = graphics.transform_mut(planet) {
+            transform.translation = Position::new(1.5, 0.0, 0.0, 1.0);
+        }
+        if let Some(transform) = graphics.transform_mut(moon) {
+            transform.translation = Position::new(0.4, 0.1, 0.0, 1.0);
+        }
+
+        graphics.camera.set_position(Position::new(0.0, 1.5, 3.5, 1.0));
+        graphics.camera.pitch = -0.4;
+
+        self.sun = Some(sun);
+        self.planet = Some(planet);
+    }
+
+    fn update(&mut self, context: &mut Context) {
+        let elapsed = context.time.elapsed;
+        let up = Normal::new(0.0, 1.0, 0.0);
+
+        // turning a parent swings its children around with it
+        for (id, speed) in [(self.sun, 0.5), (self.planet, 2.0)] {
+            let Some(transform) = id.and_then(|id| context.graphics.transform_mut(id)) else { continue };
+            transform.rotation = Quat::from_axis_angle(up, elapsed * speed);
+        }
+    }
+}
+
+fn main() {
+    let solar = Solar { sun: None, planet: None };
+
+    let _ = pollster::block_on(run(solar));
+}
@@ -1,17 +1,43 @@
 mod graphics;
 mod gui;
+mod camera;
+mod texture;
+mod scene;
+mod app;
+mod light;
 mod mesh;
 mod color;
 mod vertex;
 mod position;
 mod normal;
+mod vec3;
 mod primitives;
+mod debug;
+mod buffer;
+mod instance;
+mod bounds;
+mod mat4;
+mod quat;
+mod transform;
 
 pub use self::graphics::*;
 pub use self::gui::*;
+pub use self::camera::*;
+pub use self::texture::*;
+pub use self::scene::*;
+pub use self::app::*;
+pub use self::light::*;
 pub use self::mesh::*;
 pub use self::color::*;
 pub use self::vertex::*;
 pub use self::position::*;
 pub use self::normal::*;
-pub use self::primitives::*;
\ No newline at end of file
+pub use self::vec3::*;
+pub use self::primitives::*;
+pub use self::debug::*;
+pub use self::buffer::*;
+pub use self::instance::*;
+pub use self::bounds::*;
+pub use self::mat4::*;
+pub use self::quat::*;
+pub use self::transform::*;
\ No newline at end of file
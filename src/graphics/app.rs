@@ -0,0 +1,199 @@
+use winit::event::WindowEvent;
+use crate::graphics::Graphics;
+use crate::graphics::Geometry;
+use crate::graphics::Mesh;
+
+// timing information passed to every app callback, in seconds
+#[derive(Debug, Clone, Copy)]
+pub struct Time {
+    pub delta: f32,
+    pub elapsed: f32,
+    pub fixed_delta: f32,
+    // how far the current frame is between the last and the next fixed update, 0..1
+    pub alpha: f32,
+    pub frame: u64,
+}
+
+// state handed to the app each time run calls into it
+pub struct Context<'a> {
+    pub graphics: &'a mut Graphics,
+    pub time: Time,
+}
+
+// hooks driven by run, every hook has an empty default
+pub trait App {
+    // called once after the graphics have been created
+    fn init(&mut self, _context: &mut Context) {}
+
+    // called zero or more times per frame at a fixed rate for simulation
+    fn fixed_update(&mut self, _context: &mut Context) {}
+
+    // called once per frame before rendering
+    fn update(&mut self, _context: &mut Context) {}
+
+    // called once per frame just before the scene is drawn
+    fn render(&mut self, _context: &mut Context) {}
+
+    // called for every window event, returning true stops the default handling
+    fn on_event(&mut self, _context: &mut Context, _event: &WindowEvent) -> bool {
+        false
+    }
+}
+
+// shortest fixed step the clock will take, a zero or negative step would never use up the accumulated time
+pub const MIN_FIXED_DELTA: f32 = 1.0 / 10000.0;
+
+// seconds since the first call, std's clock panics in the browser so it reads the page's performance timer there
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> f64 {
+    static START: std::sync::OnceLock<std::time::Instant> = std::sync::OnceLock::new();
+
+    START.get_or_init(std::time::Instant::now).elapsed().as_secs_f64()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn now() -> f64 {
+    web_sys::window()
+        .and_then(|window| window.performance())
+        .map(|performance| performance.now() / 1000.0)
+        .unwrap_or(0.0)
+}
+
+// fixed timestep clock that accumulates frame time into simulation steps
+#[derive(Debug)]
+pub struct Clock {
+    // clamped to at least MIN_FIXED_DELTA when stepping
+    pub fixed_delta: f32,
+    pub max_frame_time: f32,
+    start: f64,
+    last_frame: f64,
+    accumulator: f32,
+    delta: f32,
+    frame: u64,
+    steps: u64,
+}
+
+impl Clock {
+    pub fn new(fixed_delta: f32) -> Self {
+        let now = now();
+
+        Self {
+            fixed_delta,
+            // clamp long frames so a stall doesn't trigger a burst of fixed updates
+            max_frame_time: 0.25,
+            start: now,
+            last_frame: now,
+            accumulator: 0.0,
+            delta: 0.0,
+            frame: 0,
+            steps: 0,
+        }
+    }
+
+    // start a new frame, measuring the time since the previous one
+    pub fn tick(&mut self) {
+        let now = now();
+        let delta = (now - self.last_frame).max(0.0) as f32;
+        self.last_frame = now;
+
+        self.advance(delta);
+    }
+
+    // length of a fixed step, never zero or negative
+    fn fixed_step(&self) -> f32 {
+        self.fixed_delta.max(MIN_FIXED_DELTA)
+    }
+
+    // advance the clock by a frame of the given length
+    pub fn advance(&mut self, delta: f32) {
+        self.delta = delta.min(self.max_frame_time);
+        self.accumulator += self.delta;
+        self.frame += 1;
+    }
+
+    // consume one fixed step if enough time has accumulated
+    pub fn step(&mut self) -> bool {
+        let fixed_step = self.fixed_step();
+
+        if self.accumulator >= fixed_step {
+            self.accumulator -= fixed_step;
+            self.steps += 1;
+            return true;
+        }
+
+        false
+    }
+
+    // timing for the variable rate callbacks
+    pub fn time(&self) -> Time {
+        Time {
+            delta: self.delta,
+            elapsed: (self.last_frame - self.start) as f32,
+            fixed_delta: self.fixed_step(),
+            alpha: self.accumulator / self.fixed_step(),
+            frame: self.frame,
+        }
+    }
+
+    // timing for the fixed rate callback, where the elapsed time is the simulation time stepped through so far
+    pub fn fixed_time(&self) -> Time {
+        Time {
+            delta: self.fixed_step(),
+            elapsed: (self.steps as f64 * self.fixed_step() as f64) as f32,
+            ..self.time()
+        }
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new(1.0 / 60.0)
+    }
+}
+
+// a lone primitive is an app that adds itself to the scene
+impl App for Geometry {
+    fn init(&mut self, context: &mut Context) {
+        context.graphics.add_geometry(self.clone());
+    }
+}
+
+impl App for Mesh {
+    fn init(&mut self, context: &mut Context) {
+        context.graphics.add_mesh(self.clone(), self.centroid());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_time_counts_simulation_steps() {
+        let mut clock = Clock::new(0.1);
+        clock.advance(0.25);
+
+        let mut steps = 0;
+        while clock.step() {
+            steps += 1;
+            assert!((clock.fixed_time().elapsed - steps as f32 * 0.1).abs() < 1e-6);
+        }
+
+        assert_eq!(steps, 2);
+        assert!((clock.time().alpha - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn zero_fixed_delta_still_ends() {
+        let mut clock = Clock::new(0.0);
+        clock.advance(0.01);
+
+        let mut steps = 0;
+        while clock.step() {
+            steps += 1;
+        }
+
+        assert_eq!(steps, (0.01 / MIN_FIXED_DELTA) as usize);
+        assert_eq!(clock.fixed_time().delta, MIN_FIXED_DELTA);
+    }
+}
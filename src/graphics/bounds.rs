@@ -0,0 +1,303 @@
+use crate::graphics::{Position, Normal, Camera, Mat4};
+
+// axis aligned box around a set of points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Position,
+    pub max: Position,
+}
+
+impl Aabb {
+    pub fn new(min: Position, max: Position) -> Self {
+        Self { min, max }
+    }
+
+    // smallest box holding every point, or a point at the origin when there are none
+    pub fn from_points(points: impl IntoIterator<Item = Position>) -> Self {
+        let mut points = points.into_iter();
+        let Some(first) = points.next() else {
+            let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+            return Self::new(origin, origin);
+        };
+
+        let mut min = [first.x, first.y, first.z];
+        let mut max = min;
+        for point in points {
+            for (axis, value) in [point.x, point.y, point.z].into_iter().enumerate() {
+                min[axis] = min[axis].min(value);
+                max[axis] = max[axis].max(value);
+            }
+        }
+
+        Self::new(Position::new(min[0], min[1], min[2], 1.0), Position::new(max[0], max[1], max[2], 1.0))
+    }
+
+    pub fn center(&self) -> Position {
+        Position::new(
+            (self.min.x + self.max.x) / 2.0,
+            (self.min.y + self.max.y) / 2.0,
+            (self.min.z + self.max.z) / 2.0,
+            1.0,
+        )
+    }
+
+    // half the size of the box along each axis
+    pub fn extents(&self) -> Normal {
+        Normal::new(
+            (self.max.x - self.min.x) / 2.0,
+            (self.max.y - self.min.y) / 2.0,
+            (self.max.z - self.min.z) / 2.0,
+        )
+    }
+
+    pub fn contains(&self, point: Position) -> bool {
+        point.x >= self.min.x && point.x <= self.max.x
+            && point.y >= self.min.y && point.y <= self.max.y
+            && point.z >= self.min.z && point.z <= self.max.z
+    }
+
+    pub fn intersects(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x && self.max.x >= other.min.x
+            && self.min.y <= other.max.y && self.max.y >= other.min.y
+            && self.min.z <= other.max.z && self.max.z >= other.min.z
+    }
+
+    // smallest box holding both boxes
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb::from_points([self.min, self.max, other.min, other.max])
+    }
+
+    pub fn translate(&mut self, offset: Position) {
+        self.min = Position::new(self.min.x + offset.x, self.min.y + offset.y, self.min.z + offset.z, 1.0);
+        self.max = Position::new(self.max.x + offset.x, self.max.y + offset.y, self.max.z + offset.z, 1.0);
+    }
+
+    // box around this box once it has been through a row-vector transform
+    pub fn transformed(&self, transform: Mat4) -> Aabb {
+        let min = [self.min.x, self.min.y, self.min.z];
+        let max = [self.max.x, self.max.y, self.max.z];
+        let mut new_min = [transform[3][0], transform[3][1], transform[3][2]];
+        let mut new_max = new_min;
+
+        // each input axis pushes each output axis by its smallest and largest contribution
+        for (i, row) in transform.0.iter().enumerate().take(3) {
+            for j in 0..3 {
+                let a = row[j] * min[i];
+                let b = row[j] * max[i];
+                new_min[j] += a.min(b);
+                new_max[j] += a.max(b);
+            }
+        }
+
+        Aabb::new(Position::new(new_min[0], new_min[1], new_min[2], 1.0), Position::new(new_max[0], new_max[1], new_max[2], 1.0))
+    }
+}
+
+// sphere around a set of points
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Position,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    pub fn new(center: Position, radius: f32) -> Self {
+        Self { center, radius }
+    }
+
+    // sphere centered on the box around the points, just reaching the furthest one
+    pub fn from_points(points: &[Position]) -> Self {
+        let center = Aabb::from_points(points.iter().copied()).center();
+        let radius = points.iter()
+            .map(|point| Normal::new(point.x - center.x, point.y - center.y, point.z - center.z).length())
+            .fold(0.0, f32::max);
+
+        Self::new(center, radius)
+    }
+
+    pub fn contains(&self, point: Position) -> bool {
+        Normal::new(point.x - self.center.x, point.y - self.center.y, point.z - self.center.z).length() <= self.radius
+    }
+
+    // smallest sphere holding both spheres
+    pub fn union(&self, other: &BoundingSphere) -> BoundingSphere {
+        let offset = Normal::new(other.center.x - self.center.x, other.center.y - self.center.y, other.center.z - self.center.z);
+        let distance = offset.length();
+
+        if distance + other.radius <= self.radius {
+            return *self;
+        }
+        if distance + self.radius <= other.radius {
+            return *other;
+        }
+
+        // stretch from the far side of one to the far side of the other
+        let radius = (distance + self.radius + other.radius) / 2.0;
+        let center = self.center + offset * ((radius - self.radius) / distance);
+
+        BoundingSphere::new(Position::new(center.x, center.y, center.z, 1.0), radius)
+    }
+
+    pub fn translate(&mut self, offset: Position) {
+        self.center = Position::new(self.center.x + offset.x, self.center.y + offset.y, self.center.z + offset.z, 1.0);
+    }
+
+    // sphere around this sphere once it has been through a row-vector transform, grown by the largest scale
+    pub fn transformed(&self, transform: Mat4) -> BoundingSphere {
+        let center = Position::new(self.center.x, self.center.y, self.center.z, 1.0) * transform;
+        let scale = transform.0.iter()
+            .take(3)
+            .map(|row| Normal::new(row[0], row[1], row[2]).length())
+            .fold(0.0, f32::max);
+
+        BoundingSphere::new(center, self.radius * scale)
+    }
+}
+
+// plane holding the points where normal . point + distance is zero, with the normal pointing inside
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal: Normal,
+    pub distance: f32,
+}
+
+impl Plane {
+    pub fn new(normal: Normal, distance: f32) -> Self {
+        Self { normal, distance }
+    }
+
+    // plane from the coefficients of a x + b y + c z + d, scaled so the normal has unit length
+    pub fn from_coefficients(coefficients: [f32; 4]) -> Self {
+        let normal = Normal::new(coefficients[0], coefficients[1], coefficients[2]);
+        let length = normal.length();
+
+        if length == 0.0 {
+            return Self::new(normal, coefficients[3]);
+        }
+
+        Self::new(normal / length, coefficients[3] / length)
+    }
+
+    // signed distance of a point from the plane, positive on the inside
+    pub fn distance_to(&self, point: Position) -> f32 {
+        self.normal[0] * point.x + self.normal[1] * point.y + self.normal[2] * point.z + self.distance
+    }
+}
+
+// the six planes bounding what a camera can see
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    // left, right, bottom, top, near, far
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    pub fn new(camera: &Camera) -> Self {
+        Self::from_matrix(camera.view_projection())
+    }
+
+    // extract the planes from a row-vector view projection with wgpu's 0..1 depth range
+    pub fn from_matrix(view_projection: Mat4) -> Self {
+        // clip space x, y, z and w are the dot products of the point with each column
+        let column = |j: usize| [view_projection[0][j], view_projection[1][j], view_projection[2][j], view_projection[3][j]];
+        let [x, y, z, w] = [column(0), column(1), column(2), column(3)];
+
+        let add = |a: [f32; 4], b: [f32; 4]| [a[0] + b[0], a[1] + b[1], a[2] + b[2], a[3] + b[3]];
+        let sub = |a: [f32; 4], b: [f32; 4]| [a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]];
+
+        Self {
+            planes: [add(w, x), sub(w, x), add(w, y), sub(w, y), z, sub(w, z)].map(Plane::from_coefficients),
+        }
+    }
+
+    pub fn contains(&self, point: Position) -> bool {
+        self.planes.iter().all(|plane| plane.distance_to(point) >= 0.0)
+    }
+
+    // false only when the sphere is wholly outside one of the planes
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes.iter().all(|plane| plane.distance_to(sphere.center) >= -sphere.radius)
+    }
+
+    // false only when the box is wholly outside one of the planes
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            // the corner furthest along the plane normal
+            let corner = Position::new(
+                if plane.normal[0] >= 0.0 { aabb.max.x } else { aabb.min.x },
+                if plane.normal[1] >= 0.0 { aabb.max.y } else { aabb.min.y },
+                if plane.normal[2] >= 0.0 { aabb.max.z } else { aabb.min.z },
+                1.0,
+            );
+
+            plane.distance_to(corner) >= 0.0
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{Mesh, Vertex, Color, Vec3};
+
+    #[test]
+    fn transformed_aabb_holds_rotated_corners() {
+        let aabb = Aabb::new(Position::new(-1.0, -2.0, -3.0, 1.0), Position::new(1.0, 2.0, 3.0, 1.0));
+
+        // quarter turn around z then a move along x
+        let transform = Mat4::new([
+            [0.0, 1.0, 0.0, 0.0],
+            [-1.0, 0.0, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+            [5.0, 0.0, 0.0, 1.0],
+        ]);
+        let moved = aabb.transformed(transform);
+
+        assert_eq!(moved.min, Position::new(3.0, -1.0, -3.0, 1.0));
+        assert_eq!(moved.max, Position::new(7.0, 1.0, 3.0, 1.0));
+    }
+
+    #[test]
+    fn mesh_bounds_follow_translate_and_rotate() {
+        let normal = Normal::new(0.0, 0.0, 1.0);
+        let mut mesh = Mesh::new(vec![
+            Vertex::new(Position::new(0.0, 0.0, 0.0, 1.0), Color::white(), normal),
+            Vertex::new(Position::new(2.0, 0.0, 0.0, 1.0), Color::white(), normal),
+            Vertex::new(Position::new(0.0, 1.0, 0.0, 1.0), Color::white(), normal),
+        ], vec![0, 1, 2]);
+
+        assert_eq!(mesh.aabb.max, Position::new(2.0, 1.0, 0.0, 1.0));
+        assert_eq!(mesh.bounding_sphere.center, Position::new(1.0, 0.5, 0.0, 1.0));
+
+        mesh.translate(Position::new(1.0, 1.0, 1.0, 0.0));
+        assert_eq!(mesh.aabb.min, Position::new(1.0, 1.0, 1.0, 1.0));
+        assert_eq!(mesh.bounding_sphere.center, Position::new(2.0, 1.5, 1.0, 1.0));
+
+        // every vertex stays inside both volumes after a rotation
+        mesh.rotate(Vec3::Z, Position::new(0.0, 0.0, 0.0, 1.0), 1.0);
+        for vertex in &mesh.vertices {
+            assert!(mesh.aabb.contains(vertex.position));
+            assert!(mesh.bounding_sphere.radius >= Normal::from(vertex.position - mesh.bounding_sphere.center).length() - 1e-5);
+        }
+    }
+
+    #[test]
+    fn frustum_culls_behind_and_beside_the_camera() {
+        // the default camera sits at z = 2 looking down -z
+        let frustum = Frustum::new(&Camera::default());
+
+        let ahead = BoundingSphere::new(Position::new(0.0, 0.0, 0.0, 1.0), 0.5);
+        let behind = BoundingSphere::new(Position::new(0.0, 0.0, 5.0, 1.0), 0.5);
+        let beside = BoundingSphere::new(Position::new(50.0, 0.0, 0.0, 1.0), 0.5);
+        let beyond = BoundingSphere::new(Position::new(0.0, 0.0, -2000.0, 1.0), 0.5);
+
+        assert!(frustum.intersects_sphere(&ahead));
+        assert!(!frustum.intersects_sphere(&behind));
+        assert!(!frustum.intersects_sphere(&beside));
+        assert!(!frustum.intersects_sphere(&beyond));
+
+        let straddling = Aabb::new(Position::new(-100.0, -0.1, -0.1, 1.0), Position::new(100.0, 0.1, 0.1, 1.0));
+        assert!(frustum.intersects_aabb(&straddling));
+        assert!(!frustum.intersects_aabb(&Aabb::new(Position::new(40.0, 0.0, 0.0, 1.0), Position::new(41.0, 1.0, 1.0, 1.0))));
+    }
+}
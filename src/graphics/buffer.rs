@@ -0,0 +1,121 @@
+// gpu buffer written with queue.write_buffer, replaced by a bigger one only when the contents outgrow it
+#[derive(Debug)]
+pub struct GrowableBuffer {
+    pub label: &'static str,
+    pub usage: wgpu::BufferUsages,
+    buffer: Option<wgpu::Buffer>,
+    // bytes written by the last write
+    len: wgpu::BufferAddress,
+}
+
+impl GrowableBuffer {
+    // the buffer itself is created by the first write
+    pub fn new(label: &'static str, usage: wgpu::BufferUsages) -> Self {
+        Self {
+            label,
+            usage,
+            buffer: None,
+            len: 0,
+        }
+    }
+
+    // size a buffer is created with to hold the given number of bytes, leaving room to grow
+    pub fn capacity_for(size: wgpu::BufferAddress) -> wgpu::BufferAddress {
+        size.max(wgpu::COPY_BUFFER_ALIGNMENT).next_power_of_two()
+    }
+
+    pub fn capacity(&self) -> wgpu::BufferAddress {
+        self.buffer.as_ref().map(|buffer| buffer.size()).unwrap_or(0)
+    }
+
+    pub fn len(&self) -> wgpu::BufferAddress {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn buffer(&self) -> Option<&wgpu::Buffer> {
+        self.buffer.as_ref()
+    }
+
+    // the part of the buffer holding the last contents written
+    pub fn slice(&self) -> Option<wgpu::BufferSlice<'_>> {
+        match &self.buffer {
+            Some(buffer) if self.len > 0 => Some(buffer.slice(..self.len)),
+            _ => None,
+        }
+    }
+
+    // copy the contents into the buffer, returning true when a new buffer had to be created
+    pub fn write(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, contents: &[u8]) -> bool {
+        let size = contents.len() as wgpu::BufferAddress;
+        self.len = size;
+
+        if size == 0 {
+            return false;
+        }
+
+        let grown = self.capacity() < size;
+        if grown {
+            self.buffer = Some(device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(self.label),
+                size: Self::capacity_for(size),
+                usage: self.usage | wgpu::BufferUsages::COPY_DST,
+                mapped_at_creation: false,
+            }));
+        }
+
+        if let Some(buffer) = &self.buffer {
+            queue.write_buffer(buffer, 0, contents);
+        }
+
+        grown
+    }
+}
+
+// buffers given back by removed objects, handed out again before any new ones are created
+#[derive(Debug, Default)]
+pub struct BufferPool {
+    free: Vec<GrowableBuffer>,
+}
+
+impl BufferPool {
+    pub fn new() -> Self {
+        Self { free: Vec::new() }
+    }
+
+    // the smallest free buffer with the usage that can hold size bytes, or a new one
+    pub fn take(&mut self, label: &'static str, usage: wgpu::BufferUsages, size: wgpu::BufferAddress) -> GrowableBuffer {
+        let best = self.free.iter()
+            .enumerate()
+            .filter(|(_, buffer)| buffer.usage == usage && buffer.capacity() >= size)
+            .min_by_key(|(_, buffer)| buffer.capacity())
+            .map(|(index, _)| index);
+
+        match best {
+            Some(index) => {
+                let mut buffer = self.free.swap_remove(index);
+                buffer.label = label;
+                buffer.len = 0;
+                buffer
+            },
+            None => GrowableBuffer::new(label, usage),
+        }
+    }
+
+    pub fn give(&mut self, buffer: GrowableBuffer) {
+        if buffer.buffer.is_some() {
+            self.free.push(buffer);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+}
@@ -0,0 +1,160 @@
+use crate::graphics::{Position, Normal, Mat4};
+
+// free-flying perspective camera, oriented by yaw (around y), pitch (around x) and roll (around the view direction)
+#[derive(Debug, Clone, Copy)]
+pub struct Camera {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    pub fovy: f32,
+    pub aspect: f32,
+    pub znear: f32,
+    pub zfar: f32,
+}
+
+impl Camera {
+    pub fn new(position: Position, yaw: f32, pitch: f32, roll: f32, aspect: f32) -> Self {
+        Self {
+            x: position.x,
+            y: position.y,
+            z: position.z,
+            yaw,
+            pitch,
+            roll,
+            fovy: 45.0_f32.to_radians(),
+            aspect,
+            znear: 0.1,
+            zfar: 1000.0,
+        }
+    }
+
+    // get the position of the camera as a point
+    pub fn position(&self) -> Position {
+        Position::new(self.x, self.y, self.z, 1.0)
+    }
+
+    // move the camera to a new position
+    pub fn set_position(&mut self, position: Position) {
+        self.x = position.x;
+        self.y = position.y;
+        self.z = position.z;
+    }
+
+    // update the aspect ratio from the surface size
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if height > 0 {
+            self.aspect = width as f32 / height as f32;
+        }
+    }
+
+    // direction the camera is looking in, yaw 0 and pitch 0 look down -z
+    pub fn forward(&self) -> [f32; 3] {
+        [
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            -self.pitch.cos() * self.yaw.cos(),
+        ]
+    }
+
+    // right and up axes of the camera after applying roll
+    pub fn axes(&self) -> ([f32; 3], [f32; 3], [f32; 3]) {
+        let forward = self.forward();
+        let right = normalize(cross(forward, [0.0, 1.0, 0.0]));
+        let up = cross(right, forward);
+
+        // roll the right and up axes around the forward axis
+        let (sin, cos) = self.roll.sin_cos();
+        let rolled_right = [
+            right[0] * cos + up[0] * sin,
+            right[1] * cos + up[1] * sin,
+            right[2] * cos + up[2] * sin,
+        ];
+        let rolled_up = [
+            up[0] * cos - right[0] * sin,
+            up[1] * cos - right[1] * sin,
+            up[2] * cos - right[2] * sin,
+        ];
+
+        (rolled_right, rolled_up, forward)
+    }
+
+    // world to view space transform, laid out for row vectors (position * matrix)
+    pub fn view_matrix(&self) -> Mat4 {
+        let (right, up, forward) = self.axes();
+
+        Mat4::view(self.position(), Normal::from(right), Normal::from(up), Normal::from(forward))
+    }
+
+    // view to clip space transform with wgpu's 0..1 depth range
+    pub fn projection_matrix(&self) -> Mat4 {
+        Mat4::perspective(self.fovy, self.aspect, self.znear, self.zfar)
+    }
+
+    // combined world to clip space transform
+    pub fn view_projection(&self) -> Mat4 {
+        self.view_matrix() * self.projection_matrix()
+    }
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self::new(Position::new(0.0, 0.0, 2.0, 1.0), 0.0, 0.0, 0.0, 4.0 / 3.0)
+    }
+}
+
+// camera matrices as laid out in the shader's camera uniform
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct CameraUniform {
+    pub view: Mat4,
+    pub projection: Mat4,
+    pub view_projection: Mat4,
+    pub position: [f32; 4],
+}
+
+impl CameraUniform {
+    pub fn new(camera: &Camera) -> Self {
+        let mut uniform = Self {
+            view: Mat4::IDENTITY,
+            projection: Mat4::IDENTITY,
+            view_projection: Mat4::IDENTITY,
+            position: [0.0, 0.0, 0.0, 1.0],
+        };
+        uniform.update(camera);
+
+        uniform
+    }
+
+    // copy the current camera state into the uniform
+    pub fn update(&mut self, camera: &Camera) {
+        self.view = camera.view_matrix();
+        self.projection = camera.projection_matrix();
+        self.view_projection = self.view * self.projection;
+        self.position = [camera.x, camera.y, camera.z, 1.0];
+    }
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+fn normalize(v: [f32; 3]) -> [f32; 3] {
+    let length = dot(v, v).sqrt();
+
+    if length == 0.0 {
+        return v;
+    }
+
+    [v[0] / length, v[1] / length, v[2] / length]
+}
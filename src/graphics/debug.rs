@@ -0,0 +1,432 @@
+use std::collections::HashSet;
+use crate::graphics::{Mesh, Topology, Vertex, Color, Normal, Position};
+use crate::graphics::{Line, LineWidth, Facing, Ring, Spherical};
+use crate::graphics::{GrowableBuffer, Mat4};
+
+// color used for the wireframe overlay
+pub const WIREFRAME_COLOR: Color = Color::white();
+
+// color used for the normal overlay
+pub const NORMAL_COLOR: Color = Color::yellow();
+
+// replaces the vertex colors of every mesh to show how it is built
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Coloring {
+    #[default]
+    None,
+    // a distinct color per vertex index
+    VertexIndex,
+    // a distinct color per triangle
+    FaceId,
+}
+
+impl Coloring {
+    // step through the colorings in order
+    pub fn next(self) -> Self {
+        match self {
+            Coloring::None => Coloring::VertexIndex,
+            Coloring::VertexIndex => Coloring::FaceId,
+            Coloring::FaceId => Coloring::None,
+        }
+    }
+}
+
+// debug render modes, toggled from the keyboard while running
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DebugView {
+    pub wireframe: bool,
+    pub normals: bool,
+    pub normal_length: f32,
+    pub coloring: Coloring,
+}
+
+impl Default for DebugView {
+    fn default() -> Self {
+        Self {
+            wireframe: false,
+            normals: false,
+            normal_length: 0.1,
+            coloring: Coloring::None,
+        }
+    }
+}
+
+// spread colors around the hue wheel by the golden ratio so neighbouring ids stand apart
+pub fn palette(id: u32) -> Color {
+    let hue = (id as f32 * 0.618_034).fract() * 6.0;
+    let (saturation, value) = (0.65, 0.95);
+
+    let chroma = value * saturation;
+    let x = chroma * (1.0 - (hue % 2.0 - 1.0).abs());
+    let (r, g, b) = match hue as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let m = value - chroma;
+
+    Color::new(r + m, g + m, b + m, 1.0)
+}
+
+impl Mesh {
+    // every triangle edge once, as a line list, with strips unrolled and nothing for points and lines
+    pub fn wireframe(&self, color: Color) -> Mesh {
+        let mut edges: HashSet<(u32, u32)> = HashSet::new();
+        let mut indices: Vec<u32> = Vec::new();
+
+        for triangle in self.triangles() {
+            for k in 0..3 {
+                let (a, b) = (triangle[k], triangle[(k + 1) % 3]);
+
+                if edges.insert((a.min(b), a.max(b))) {
+                    indices.extend_from_slice(&[a, b]);
+                }
+            }
+        }
+
+        // lines are drawn unlit, so their normals are left at zero
+        let vertices = self.vertices.iter()
+            .map(|vertex| Vertex::new(vertex.position, color, Normal::new(0.0, 0.0, 0.0)))
+            .collect();
+
+        Mesh::with_topology(vertices, indices, Topology::LineList)
+    }
+
+    // a short line out of every vertex along its normal
+    pub fn normal_lines(&self, length: f32, color: Color) -> Mesh {
+        let mut vertices: Vec<Vertex> = Vec::with_capacity(self.vertices.len() * 2);
+
+        for vertex in &self.vertices {
+            let tip = vertex.position + vertex.normal.normalize() * length;
+
+            vertices.push(Vertex::new(vertex.position, color, Normal::new(0.0, 0.0, 0.0)));
+            vertices.push(Vertex::new(tip, color, Normal::new(0.0, 0.0, 0.0)));
+        }
+
+        let indices = (0..Mesh::index_of(vertices.len())).collect();
+
+        Mesh::with_topology(vertices, indices, Topology::LineList)
+    }
+
+    // copy of the mesh with its colors replaced, unwelding the triangles to color them by face
+    pub fn debug_colored(&self, coloring: Coloring) -> Mesh {
+        let unlit = |vertex: &Vertex, id: u32| Vertex::new(vertex.position, palette(id), Normal::new(0.0, 0.0, 0.0));
+
+        match coloring {
+            Coloring::None => self.clone(),
+            Coloring::VertexIndex => {
+                let vertices = self.vertices.iter()
+                    .enumerate()
+                    .map(|(i, vertex)| unlit(vertex, i as u32))
+                    .collect();
+
+                Mesh::with_topology(vertices, self.indices.clone(), self.topology)
+            }
+            Coloring::FaceId => {
+                if self.topology != Topology::TriangleList {
+                    return self.clone();
+                }
+
+                let vertices: Vec<Vertex> = self.indices.iter()
+                    .enumerate()
+                    .map(|(i, index)| unlit(&self.vertices[*index as usize], i as u32 / 3))
+                    .collect();
+                let indices = (0..Mesh::index_of(vertices.len())).collect();
+
+                Mesh::with_topology(vertices, indices, self.topology)
+            }
+        }
+    }
+}
+
+// segments drawn around a debug circle
+const DEBUG_CIRCLE_SEGMENTS: u32 = 32;
+
+// shapes drawn by game code during a frame, drawn over the scene and cleared once the frame is done
+#[derive(Debug)]
+pub struct DebugDraw {
+    pub width: LineWidth,
+    segments: Vec<(Position, Position, Color)>,
+    // scratch space kept between frames so drawing doesn't allocate once it has grown
+    edges: HashSet<(u32, u32)>,
+    vertices: Vec<Vertex>,
+    indices: Vec<u32>,
+    vertex_buffer: GrowableBuffer,
+    index_buffer: GrowableBuffer,
+    index_format: wgpu::IndexFormat,
+    n_indices: u32,
+}
+
+impl DebugDraw {
+    pub fn new() -> Self {
+        Self {
+            width: LineWidth::Pixels(2.0),
+            segments: Vec::new(),
+            edges: HashSet::new(),
+            vertices: Vec::new(),
+            indices: Vec::new(),
+            vertex_buffer: GrowableBuffer::new("Debug Vertex Buffer", wgpu::BufferUsages::VERTEX),
+            index_buffer: GrowableBuffer::new("Debug Index Buffer", wgpu::BufferUsages::INDEX),
+            index_format: wgpu::IndexFormat::Uint32,
+            n_indices: 0,
+        }
+    }
+
+    pub fn line(&mut self, a: Position, b: Position, color: Color) {
+        self.segments.push((a, b, color));
+    }
+
+    // every line of a line list mesh, keeping the color of its first vertex
+    pub fn lines(&mut self, mesh: &Mesh) {
+        for line in mesh.indices.chunks_exact(2) {
+            let a = mesh.vertices[line[0] as usize];
+            let b = mesh.vertices[line[1] as usize];
+
+            self.line(a.position, b.position, a.color);
+        }
+    }
+
+    // every triangle edge of a mesh once, placed by a transform
+    pub fn wireframe(&mut self, mesh: &Mesh, transform: Mat4, color: Color) {
+        self.edges.clear();
+
+        for triangle in mesh.triangles() {
+            for k in 0..3 {
+                let (a, b) = (triangle[k], triangle[(k + 1) % 3]);
+
+                if self.edges.insert((a.min(b), a.max(b))) {
+                    let [a, b] = [a, b].map(|index| mesh.vertices[index as usize].position * transform);
+                    self.segments.push((a, b, color));
+                }
+            }
+        }
+    }
+
+    // a short line out of every vertex of a mesh along its normal, placed by a transform
+    pub fn normals(&mut self, mesh: &Mesh, transform: Mat4, length: f32, color: Color) {
+        let normal = transform.normal_matrix();
+
+        for vertex in &mesh.vertices {
+            let position = vertex.position * transform;
+            let direction = Normal::from(vertex.normal.to_vec4() * normal).normalize();
+
+            self.line(position, position + direction * length, color);
+        }
+    }
+
+    // the twelve edges of an axis aligned box
+    pub fn aabb(&mut self, min: Position, max: Position, color: Color) {
+        let corner = |i: u32| Position::new(
+            if i & 1 == 0 { min.x } else { max.x },
+            if i & 2 == 0 { min.y } else { max.y },
+            if i & 4 == 0 { min.z } else { max.z },
+            1.0,
+        );
+
+        // corners one bit apart share an edge
+        for i in 0..8 {
+            for bit in [1, 2, 4] {
+                if i & bit == 0 {
+                    self.line(corner(i), corner(i | bit), color);
+                }
+            }
+        }
+    }
+
+    // outline of a circle facing along an axis
+    pub fn circle(&mut self, center: Position, radius: f32, axis: Normal, color: Color) {
+        // a ring without thickness has its inner and outer edges on the circle
+        let ring = Ring::annulus(center, radius, 0.0, DEBUG_CIRCLE_SEGMENTS, color, 0.0, 2.0 * std::f32::consts::PI, axis);
+        let points: Vec<Position> = ring.vertices.iter().step_by(2).map(|vertex| vertex.position).collect();
+
+        for i in 0..points.len() {
+            self.line(points[i], points[(i + 1) % points.len()], color);
+        }
+    }
+
+    // wireframe of a coarse uv sphere
+    pub fn sphere(&mut self, center: Position, radius: f32, color: Color) {
+        self.wireframe(&Spherical::uv_sphere(radius, center, 16, 8), Mat4::IDENTITY, color);
+    }
+
+    // line with a four sided head at the end
+    pub fn arrow(&mut self, from: Position, to: Position, color: Color) {
+        self.line(from, to, color);
+
+        let direction = Normal::new(to.x - from.x, to.y - from.y, to.z - from.z);
+        let length = direction.length();
+        if length == 0.0 {
+            return;
+        }
+
+        let direction = direction / length;
+        let reference = if direction[0].abs() < 0.9 { Normal::new(1.0, 0.0, 0.0) } else { Normal::new(0.0, 1.0, 0.0) };
+        let u = direction.cross(reference).normalize();
+        let v = direction.cross(u);
+
+        let head = length * 0.2;
+        let base = to + direction * -head;
+        for side in [u, v, u * -1.0, v * -1.0] {
+            self.line(to, base + side * (head * 0.5), color);
+        }
+    }
+
+    // the x, y and z axes of a transform in red, green and blue
+    pub fn axes(&mut self, transform: Mat4, size: f32) {
+        let origin = transform.get_translation();
+        let colors = [Color::red(), Color::green(), Color::blue()];
+
+        for (row, color) in transform.0.iter().zip(colors) {
+            let axis = Normal::new(row[0], row[1], row[2]);
+            self.arrow(origin, origin + axis * size, color);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    // forget everything drawn this frame
+    pub fn clear(&mut self) {
+        self.segments.clear();
+    }
+
+    // extrude every segment into a quad facing the given way
+    pub fn mesh(&self, facing: Facing) -> Mesh {
+        let mut vertices: Vec<Vertex> = Vec::with_capacity(self.segments.len() * 4);
+        let mut indices: Vec<u32> = Vec::with_capacity(self.segments.len() * 6);
+        self.extrude(facing, &mut vertices, &mut indices);
+
+        Mesh::new(vertices, indices)
+    }
+
+    fn extrude(&self, facing: Facing, vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>) {
+        for (a, b, color) in &self.segments {
+            Line::segment(*a, *b, *color, self.width, facing, vertices, indices);
+        }
+    }
+
+    // write this frame's shapes into the shared buffers, growing them when they run out of room
+    // the quads are built in the scratch lists and the indices are always u32, so nothing is allocated once they have grown
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, facing: Facing) {
+        let (mut vertices, mut indices) = (std::mem::take(&mut self.vertices), std::mem::take(&mut self.indices));
+        vertices.clear();
+        indices.clear();
+        self.extrude(facing, &mut vertices, &mut indices);
+
+        self.n_indices = indices.len() as u32;
+        if !indices.is_empty() {
+            self.vertex_buffer.write(device, queue, bytemuck::cast_slice(&vertices));
+            self.index_buffer.write(device, queue, bytemuck::cast_slice(&indices));
+        }
+
+        self.vertices = vertices;
+        self.indices = indices;
+    }
+
+    // draw the shapes uploaded for this frame with an overlay pipeline
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipeline: &'a wgpu::RenderPipeline) {
+        let (Some(vertices), Some(indices)) = (self.vertex_buffer.slice(), self.index_buffer.slice()) else { return };
+        if self.n_indices == 0 {
+            return;
+        }
+
+        render_pass.set_pipeline(pipeline);
+        render_pass.set_vertex_buffer(0, vertices);
+        render_pass.set_index_buffer(indices, self.index_format);
+        render_pass.draw_indexed(0..self.n_indices, 0, 0..1);
+    }
+}
+
+impl Default for DebugDraw {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{Cube, Position, STRIP_RESTART};
+
+    #[test]
+    fn test_debug_meshes_of_a_cube() {
+        let cube = Cube::cube(Position::new(0.0, 0.0, 0.0, 1.0), 1.0);
+
+        // each face is a quad of two triangles, so five edges per face
+        let wireframe = cube.mesh.wireframe(WIREFRAME_COLOR);
+        assert_eq!(wireframe.topology, Topology::LineList);
+        assert_eq!(wireframe.indices.len(), 6 * 5 * 2);
+
+        let normals = cube.mesh.normal_lines(0.5, NORMAL_COLOR);
+        assert_eq!(normals.indices.len(), cube.mesh.vertices.len() * 2);
+        for line in normals.vertices.chunks_exact(2) {
+            assert!((line[0].position.distance(line[1].position) - 0.5).abs() < 1e-5);
+        }
+
+        // every corner of a triangle shares the color of its face
+        let faces = cube.mesh.debug_colored(Coloring::FaceId);
+        assert_eq!(faces.vertices.len(), cube.mesh.indices.len());
+        for triangle in faces.vertices.chunks_exact(3) {
+            assert!(triangle[0].color == triangle[1].color && triangle[1].color == triangle[2].color);
+        }
+        assert!(faces.vertices[0].color != faces.vertices[3].color);
+    }
+
+    #[test]
+    fn test_wireframe_unrolls_strips() {
+        let vertices: Vec<Vertex> = (0..7)
+            .map(|i| Vertex::new(Position::new((i / 2) as f32, (i % 2) as f32, 0.0, 1.0), Color::white(), Normal::new(0.0, 0.0, 1.0)))
+            .collect();
+
+        // a quad of two triangles, then a single triangle after the restart
+        let strip = Mesh::with_topology(vertices, vec![0, 1, 2, 3, STRIP_RESTART, 4, 5, 6], Topology::TriangleStrip);
+        assert_eq!(strip.triangle_list(), vec![0, 1, 2, 2, 1, 3, 4, 5, 6]);
+        assert_eq!(strip.wireframe(WIREFRAME_COLOR).indices.len(), (5 + 3) * 2);
+
+        let points = Mesh::with_topology(strip.vertices.clone(), vec![0, 1, 2], Topology::PointList);
+        assert!(points.wireframe(WIREFRAME_COLOR).indices.is_empty());
+    }
+
+    #[test]
+    fn test_debug_draw_batches_segments() {
+        let mut debug = DebugDraw::new();
+        debug.width = LineWidth::World(0.1);
+
+        debug.line(Position::new(0.0, 0.0, 0.0, 1.0), Position::new(1.0, 0.0, 0.0, 1.0), Color::red());
+        debug.aabb(Position::new(-1.0, -1.0, -1.0, 1.0), Position::new(1.0, 1.0, 1.0, 1.0), Color::green());
+        debug.arrow(Position::new(0.0, 0.0, 0.0, 1.0), Position::new(0.0, 2.0, 0.0, 1.0), Color::blue());
+        debug.circle(Position::new(0.0, 0.0, 0.0, 1.0), 1.0, Normal::new(0.0, 1.0, 0.0), Color::white());
+
+        // one line, twelve box edges, a shaft with four head lines and the circle
+        let segments = 1 + 12 + 5 + DEBUG_CIRCLE_SEGMENTS as usize;
+        let mesh = debug.mesh(Facing::Fixed(Normal::new(0.0, 0.0, 1.0)));
+        assert_eq!(mesh.indices.len(), segments * 6);
+        assert!(mesh.indices.iter().all(|index| (*index as usize) < mesh.vertices.len()));
+
+        debug.clear();
+        assert!(debug.is_empty());
+    }
+
+    #[test]
+    fn test_debug_draw_places_meshes_by_transform() {
+        let cube = Cube::cube(Position::new(0.0, 0.0, 0.0, 1.0), 1.0);
+        let transform = Mat4::translation(Position::new(10.0, 0.0, 0.0, 0.0));
+        let mut debug = DebugDraw::new();
+
+        // the same edges as the wireframe mesh, moved over without copying the cube
+        debug.wireframe(&cube.mesh, transform, WIREFRAME_COLOR);
+        assert_eq!(debug.segments.len(), cube.mesh.wireframe(WIREFRAME_COLOR).indices.len() / 2);
+        assert!(debug.segments.iter().all(|(a, b, _)| a.x >= 9.0 && b.x >= 9.0));
+
+        debug.clear();
+        debug.normals(&cube.mesh, transform, 0.5, NORMAL_COLOR);
+        assert_eq!(debug.segments.len(), cube.mesh.vertices.len());
+        for (a, b, _) in &debug.segments {
+            assert!((a.distance(*b) - 0.5).abs() < 1e-5);
+        }
+    }
+}
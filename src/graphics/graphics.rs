@@ -1,13 +1,24 @@
 
+use std::collections::HashMap;
 use wgpu::util::DeviceExt;
 use winit::{
     event::*,
     event_loop::{ControlFlow, EventLoop},
     window::{WindowBuilder, Window},
 };
-use crate::graphics::Vertex;
-use crate::graphics::Position;
+use crate::graphics::{Vertex, Instance, InstanceRaw, InstancedMesh, InstancedMeshId};
+use crate::graphics::{Position, Vec3};
 use crate::graphics::Geometry;
+use crate::graphics::{Mesh, Topology};
+use crate::graphics::{Scene, Object, ObjectId, PipelineKey, ModelUniform, CullStats};
+use crate::graphics::Transform;
+use crate::types::{WorldPosition, FloatingOrigin};
+use crate::graphics::Frustum;
+use crate::graphics::{App, Clock, Context};
+use crate::graphics::{Camera, CameraUniform};
+use crate::graphics::Texture;
+use crate::graphics::{Lighting, LightUniform};
+use crate::graphics::{DebugView, DebugDraw, Facing};
 
 #[derive(Debug)]
 pub struct Mouse {
@@ -23,24 +34,40 @@ pub struct Graphics {
     pub window: Window,
     pub surface: wgpu::Surface,
     pub device: wgpu::Device,
-    pub render_pipeline: wgpu::RenderPipeline,
-    pub vertex_buffer: wgpu::Buffer,
-    pub index_buffer: wgpu::Buffer,
+    pub shader: wgpu::ShaderModule,
+    pub render_pipeline_layout: wgpu::PipelineLayout,
+    // one pipeline per topology and cull mode, built the first time an object needs it
+    pub pipelines: HashMap<PipelineKey, wgpu::RenderPipeline>,
+    pub depth_texture: Texture,
+    pub depth_compare: wgpu::CompareFunction,
     pub queue: wgpu::Queue,
     pub config: wgpu::SurfaceConfiguration,
     pub size: winit::dpi::PhysicalSize<u32>,
     pub mouse_state: Mouse,
-    pub geometry: Geometry,
-    pub n_vertices: u32,
-    pub n_indices: u32,
+    pub camera: Camera,
+    pub camera_uniform: CameraUniform,
+    pub camera_buffer: wgpu::Buffer,
+    pub camera_bind_group: wgpu::BindGroup,
+    pub lighting: Lighting,
+    pub light_uniform: LightUniform,
+    pub light_buffer: wgpu::Buffer,
+    pub light_bind_group: wgpu::BindGroup,
+    // model matrices are bound per object with a dynamic offset
+    pub model_bind_group_layout: wgpu::BindGroupLayout,
+    // model matrix for shapes given in world space, such as the debug draw
+    pub identity_model_buffer: wgpu::Buffer,
+    pub identity_model_bind_group: wgpu::BindGroup,
+    pub scene: Scene,
+    // world position render space is centred on, moved to follow the camera
+    pub floating_origin: FloatingOrigin,
+    pub debug_view: DebugView,
+    pub debug_draw: DebugDraw,
 }
 
 impl Graphics {
-    pub async fn new(window: Window, geometry: Geometry) -> Self {
+    pub async fn new(window: Window) -> Self {
         const WINDOW_HEIGHT: u32 = 1200;
         const WINDOW_WIDTH: u32 = 1600;
-        let n_vertices: u32 = geometry.vertex_len() as u32;
-        let n_indices = geometry.index_len() as u32;
 
         // Initialize logger
         cfg_if::cfg_if! {
@@ -101,7 +128,8 @@ impl Graphics {
         // create the wgpu device and queue
         let (device, queue) = adapter.request_device(
                                           &wgpu::DeviceDescriptor {
-                                              features: wgpu::Features::empty(),
+                                              // wireframes are drawn as polygon outlines where the adapter allows it
+                                              features: adapter.features() & wgpu::Features::POLYGON_MODE_LINE,
                                               limits: if cfg!(target_arch = "wasm32") {
                                                 wgpu::Limits::downlevel_webgl2_defaults()
                                                 } else {
@@ -138,98 +166,378 @@ impl Graphics {
             source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
         });
 
+        // create the camera looking at the origin with the aspect ratio of the window
+        let mut camera = Camera::default();
+        camera.resize(size.width, size.height);
+
+        // create the camera uniform buffer
+        let camera_uniform = CameraUniform::new(&camera);
+        let camera_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Camera Buffer"),
+                contents: bytemuck::cast_slice(&[camera_uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        // create the camera bind group
+        let camera_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Camera Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX | wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }
+            ],
+        });
+
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Camera Bind Group"),
+            layout: &camera_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: camera_buffer.as_entire_binding(),
+                }
+            ],
+        });
+
+        // create the light uniform buffer with the default sun and ambient light
+        let lighting = Lighting::default();
+        let light_uniform = lighting.to_uniform();
+        let light_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Light Buffer"),
+                contents: bytemuck::cast_slice(&[light_uniform]),
+                usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            }
+        );
+
+        // create the light bind group
+        let light_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Light Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                }
+            ],
+        });
+
+        let light_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Light Bind Group"),
+            layout: &light_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: light_buffer.as_entire_binding(),
+                }
+            ],
+        });
+
+        // create the model bind group layout, the scene writes every model matrix into one buffer
+        let model_bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("Model Bind Group Layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStages::VERTEX,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: true,
+                        min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<ModelUniform>() as u64),
+                    },
+                    count: None,
+                }
+            ],
+        });
+
+        // create the identity model used by anything not in the scene
+        let identity_model_buffer = device.create_buffer_init(
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("Identity Model Buffer"),
+                contents: bytemuck::cast_slice(&[ModelUniform::identity()]),
+                usage: wgpu::BufferUsages::UNIFORM,
+            }
+        );
+
+        let identity_model_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Identity Model Bind Group"),
+            layout: &model_bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: identity_model_buffer.as_entire_binding(),
+                }
+            ],
+        });
+
         // create the wgpu render pipeline
         let render_pipeline_layout =
         device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("Render Pipeline Layout"),
-            bind_group_layouts: &[],
+            bind_group_layouts: &[&camera_bind_group_layout, &light_bind_group_layout, &model_bind_group_layout],
             push_constant_ranges: &[],
         });
      
-        // create the wgpu render pipeline for our shader
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        // create the depth buffer alongside the surface
+        let depth_compare = wgpu::CompareFunction::Less;
+        let depth_texture = Texture::create_depth_texture(&device, &config, "Depth Texture");
+
+        // create the wgpu render pipeline for triangle meshes up front, the rest are built on demand
+        let mut pipelines = HashMap::new();
+        let key = PipelineKey::new(Topology::TriangleList, Topology::TriangleList.default_cull_mode());
+        pipelines.insert(key, Self::create_render_pipeline(&device, &render_pipeline_layout, &shader, config.format, depth_compare, key));
+
+        // configure the surface
+        surface.configure(&device, &config);
+        
+        // create the mouse state
+        let mouse_state = Mouse {
+            mouse_position: winit::dpi::PhysicalPosition::new(0.0, 0.0),
+            prev_mouse_position: winit::dpi::PhysicalPosition::new(0.0, 0.0),
+            l_mouse_down: false,
+            m_mouse_down: false,
+            r_mouse_down: false,
+        };
+
+        Self {
+            window,
+            surface,
+            device,
+            shader,
+            render_pipeline_layout,
+            pipelines,
+            depth_texture,
+            depth_compare,
+            queue,
+            config,
+            size,
+            mouse_state,
+            camera,
+            camera_uniform,
+            camera_buffer,
+            camera_bind_group,
+            lighting,
+            light_uniform,
+            light_buffer,
+            light_bind_group,
+            model_bind_group_layout,
+            identity_model_buffer,
+            identity_model_bind_group,
+            scene: Scene::new(),
+            floating_origin: FloatingOrigin::default(),
+            debug_view: DebugView::default(),
+            debug_draw: DebugDraw::new(),
+        }
+    }
+
+    // create the render pipeline used to draw meshes with the given depth test, topology and cull mode
+    pub fn create_render_pipeline(
+        device: &wgpu::Device,
+        layout: &wgpu::PipelineLayout,
+        shader: &wgpu::ShaderModule,
+        format: wgpu::TextureFormat,
+        depth_compare: wgpu::CompareFunction,
+        key: PipelineKey,
+    ) -> wgpu::RenderPipeline {
+        // overlays draw on top of surfaces at the same depth
+        let depth_compare = match (key.overlay, depth_compare) {
+            (true, wgpu::CompareFunction::Less) => wgpu::CompareFunction::LessEqual,
+            (true, wgpu::CompareFunction::Greater) => wgpu::CompareFunction::GreaterEqual,
+            (_, depth_compare) => depth_compare,
+        };
+
+        // instanced meshes take their transform and tint from a second, per instance buffer
+        let (vertex_entry, buffers) = if key.instanced {
+            ("vertex_instanced", vec![Vertex::desc(), InstanceRaw::desc()])
+        } else {
+            ("vertex_main", vec![Vertex::desc()])
+        };
+
+
+        device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
+            layout: Some(layout),
             vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: "vertex_main", 
-                buffers: &[Vertex::desc(),], 
+                module: shader,
+                entry_point: vertex_entry,
+                buffers: &buffers,
             },
             fragment: Some(wgpu::FragmentState { 
-                module: &shader,
-                entry_point: "fragment_main",
+                module: shader,
+                entry_point: if key.polygon_mode == wgpu::PolygonMode::Fill { "fragment_main" } else { "fragment_wireframe" },
                 targets: &[Some(wgpu::ColorTargetState { 
-                    format: config.format,
+                    format,
                     blend: Some(wgpu::BlendState::REPLACE),
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
             primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList, 
-                strip_index_format: None,
+                topology: key.topology.to_wgpu(),
+                // strips restart at the maximum index
+                strip_index_format: key.strip_index_format,
                 front_face: wgpu::FrontFace::Ccw, 
-                cull_mode: Some(wgpu::Face::Back),
-                // Setting this to anything other than Fill requires Features::NON_FILL_POLYGON_MODE
-                polygon_mode: wgpu::PolygonMode::Fill,
+                cull_mode: key.cull_mode,
+                // Setting this to Line requires Features::POLYGON_MODE_LINE
+                polygon_mode: key.polygon_mode,
                 // Requires Features::DEPTH_CLIP_CONTROL
                 unclipped_depth: false,
                 // Requires Features::CONSERVATIVE_RASTERIZATION
                 conservative: false,
             },
-            depth_stencil: None, 
+            depth_stencil: Some(wgpu::DepthStencilState {
+                format: Texture::DEPTH_FORMAT,
+                depth_write_enabled: !key.overlay,
+                depth_compare,
+                stencil: wgpu::StencilState::default(),
+                bias: wgpu::DepthBiasState::default(),
+            }),
             multisample: wgpu::MultisampleState {
                 count: 1, 
                 mask: !0, 
                 alpha_to_coverage_enabled: false, 
             },
             multiview: None, 
-        });
+        })
+    }
 
-        // create the vertex buffer that will be used to draw our shapes
-        let vertex_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Vertex Buffer"),
-                contents: bytemuck::cast_slice(geometry.vertices()),
-                usage: wgpu::BufferUsages::VERTEX,
-            }
-        );
+    // change the depth compare function, rebuilding every cached pipeline
+    pub fn set_depth_compare(&mut self, depth_compare: wgpu::CompareFunction) {
+        self.depth_compare = depth_compare;
 
-        // create the index buffer that will be used to draw our shapes
-        let index_buffer = device.create_buffer_init(
-            &wgpu::util::BufferInitDescriptor {
-                label: Some("Index Buffer"),
-                contents: bytemuck::cast_slice(geometry.indices()),
-                usage: wgpu::BufferUsages::INDEX,
-            }
-        );
+        let keys: Vec<PipelineKey> = self.pipelines.keys().copied().collect();
+        self.pipelines.clear();
+        for key in keys {
+            self.pipeline(key);
+        }
+    }
 
-        // configure the surface
-        surface.configure(&device, &config);
-        
-        // create the mouse state
-        let mouse_state = Mouse {
-            mouse_position: winit::dpi::PhysicalPosition::new(0.0, 0.0),
-            prev_mouse_position: winit::dpi::PhysicalPosition::new(0.0, 0.0),
-            l_mouse_down: false,
-            m_mouse_down: false,
-            r_mouse_down: false,
-        };
+    // get the pipeline for a topology and cull mode, building it the first time it is needed
+    pub fn pipeline(&mut self, key: PipelineKey) -> &wgpu::RenderPipeline {
+        self.pipelines.entry(key).or_insert_with(|| {
+            Self::create_render_pipeline(&self.device, &self.render_pipeline_layout, &self.shader, self.config.format, self.depth_compare, key)
+        })
+    }
 
-        Self {
-            window,
-            surface,
-            device,
-            render_pipeline,
-            vertex_buffer,
-            index_buffer,
-            queue,
-            config,
-            size,
-            mouse_state,
-            geometry,
-            n_vertices,
-            n_indices,
+    // whether the wireframe can be drawn as polygon outlines rather than generated edges
+    pub fn polygon_mode_line(&self) -> bool {
+        self.device.features().contains(wgpu::Features::POLYGON_MODE_LINE)
+    }
+
+    // change the debug render modes, recoloring the scene if needed
+    pub fn set_debug_view(&mut self, debug_view: DebugView) {
+        if debug_view.coloring != self.debug_view.coloring {
+            self.scene.set_coloring(&self.device, &self.queue, debug_view.coloring);
+        }
+
+        self.debug_view = debug_view;
+    }
+
+    // switch between the debug render modes from the keyboard
+    pub fn debug_input(&mut self, key: VirtualKeyCode) {
+        let mut debug_view = self.debug_view;
+
+        match key {
+            VirtualKeyCode::F1 => debug_view.wireframe = !debug_view.wireframe,
+            VirtualKeyCode::F2 => debug_view.normals = !debug_view.normals,
+            VirtualKeyCode::F3 => debug_view.coloring = debug_view.coloring.next(),
+            _ => return,
         }
+
+        self.set_debug_view(debug_view);
+    }
+
+    // add a primitive to the scene
+    pub fn add_geometry(&mut self, geometry: Geometry) -> ObjectId {
+        self.scene.add_geometry(&self.device, &self.queue, geometry)
+    }
+
+    // add a mesh to the scene that rotates around the given origin
+    pub fn add_mesh(&mut self, mesh: Mesh, origin: Position) -> ObjectId {
+        self.scene.add(&self.device, &self.queue, mesh, origin)
+    }
+
+    // remove an object from the scene, returning its mesh
+    pub fn remove(&mut self, id: ObjectId) -> Option<Mesh> {
+        self.scene.remove(id)
+    }
+
+    // attach an object to a parent so it moves with it, or detach it with None
+    pub fn set_parent(&mut self, child: ObjectId, parent: Option<ObjectId>) -> bool {
+        self.scene.set_parent(child, parent)
+    }
+
+    // the transform of an object relative to its parent
+    pub fn transform_mut(&mut self, id: ObjectId) -> Option<&mut Transform> {
+        self.scene.get_mut(id).map(Object::transform_mut)
+    }
+
+    // where in the world the camera is
+    pub fn camera_world_position(&self) -> WorldPosition {
+        self.floating_origin.to_world(self.camera.position())
+    }
+
+    // place the camera in the world, moving the render origin onto it once it strays too far
+    pub fn set_camera_world_position(&mut self, position: WorldPosition) {
+        if self.floating_origin.needs_rebase(position) {
+            self.rebase(position);
+        }
+
+        self.camera.set_position(self.floating_origin.to_render(position));
+    }
+
+    // centre render space on a new world position, placing the camera, lights and scene again so nothing appears to move
+    pub fn rebase(&mut self, origin: WorldPosition) {
+        let previous = self.floating_origin;
+        self.floating_origin.rebase(origin);
+
+        let current = self.floating_origin;
+        self.camera.set_position(current.carry(self.camera.position(), &previous));
+        for light in &mut self.lighting.point_lights {
+            light.position = current.carry(light.position, &previous);
+        }
+        self.scene.rebase(&previous, &current);
+    }
+
+    // place a root object in the open world, it is drawn relative to the render origin and kept in place as that moves
+    // fails when the object is missing or has a parent
+    pub fn set_world_position(&mut self, id: ObjectId, position: WorldPosition) -> bool {
+        let Some(object) = self.scene.get_mut(id) else { return false };
+
+        object.set_world_position(position, &self.floating_origin)
+    }
+
+    // add a mesh drawn once per instance in a single draw call
+    pub fn add_instanced(&mut self, mesh: Mesh, instances: Vec<Instance>) -> InstancedMeshId {
+        self.scene.add_instanced(&self.device, &self.queue, mesh, instances)
+    }
+
+    // the instanced mesh with the given id, its instances can be changed every frame
+    pub fn instanced_mut(&mut self, id: InstancedMeshId) -> Option<&mut InstancedMesh> {
+        self.scene.instanced_mut(id)
+    }
+
+    // remove an instanced mesh from the scene, returning its mesh
+    pub fn remove_instanced(&mut self, id: InstancedMeshId) -> Option<Mesh> {
+        self.scene.remove_instanced(id)
+    }
+
+    // how many objects and instances the last frame culled
+    pub fn cull_stats(&self) -> CullStats {
+        self.scene.cull_stats
     }
 
     pub fn new_window(event_loop: &EventLoop<()>) -> Window {
@@ -290,6 +598,10 @@ impl Graphics {
     }
 
     pub fn update(&mut self) {
+        // upload the latest camera and light state
+        self.update_camera();
+        self.update_lighting();
+
         let current_mouse_pos = self.mouse_state.mouse_position;
 
         // if the mouse button is pushed down
@@ -315,39 +627,49 @@ impl Graphics {
             }
 
             // create xyz axis
-            let axis = Position::new(x, y, 0.0, 1.0);
+            let axis = Vec3::new(x, y, 0.0);
 
             // convert x and y displacement to an angle in degrees
             let angle = (magnitude / 100.0) * 360.0  * 0.01;
 
-            // rotate the mesh based on the mouse position against the previous mouse position
-            self.geometry.rotate(
-                angle,
-                axis
-            );
-
-            // println!("dx: {}, dy: {}, magnitude: {}, x: {}, y: {}, angle: {}", dx, dy, magnitude, x, y, angle);
-            // println!("Cube verts: {:?}", self.cube.mesh.vertices);
-
-            // recreate the vertex buffer
-            self.vertex_buffer = self.device.create_buffer_init(
-                &wgpu::util::BufferInitDescriptor {
-                    label: Some("Vertex Buffer"),
-                    contents: bytemuck::cast_slice(self.geometry.vertices()),
-                    usage: wgpu::BufferUsages::VERTEX,
-                }
-            );
+            // rotate the scene based on the mouse position against the previous mouse position, only the model matrices change
+            self.scene.rotate(angle, axis);
         }
 
         // update the previous mouse position
         self.mouse_state.prev_mouse_position = current_mouse_pos;
     }
 
+    // write the camera matrices to the camera uniform buffer
+    pub fn update_camera(&mut self) {
+        self.camera_uniform.update(&self.camera);
+        self.queue.write_buffer(&self.camera_buffer, 0, bytemuck::cast_slice(&[self.camera_uniform]));
+    }
+
+    // replace the lights in the scene
+    pub fn set_lighting(&mut self, lighting: Lighting) {
+        self.lighting = lighting;
+        self.update_lighting();
+    }
+
+    // write the lights to the light uniform buffer
+    pub fn update_lighting(&mut self) {
+        self.light_uniform = self.lighting.to_uniform();
+        self.queue.write_buffer(&self.light_buffer, 0, bytemuck::cast_slice(&[self.light_uniform]));
+    }
+
     pub fn resize(&mut self, new_size: winit::dpi::PhysicalSize<u32>) {
         self.size = new_size;
         self.config.width = new_size.width;
         self.config.height = new_size.height;
         self.surface.configure(&self.device, &self.config);
+
+        // the depth buffer has to match the size of the surface
+        self.depth_texture = Texture::create_depth_texture(&self.device, &self.config, "Depth Texture");
+
+        // keep the projection in step with the new aspect ratio
+        self.camera.resize(new_size.width, new_size.height);
+        self.update_camera();
         
         // allows for resize if using wasm in the browser
         #[cfg(target_arch = "wasm32")]
@@ -357,6 +679,37 @@ impl Graphics {
     }
 
     pub fn render(&mut self) -> Result<(), wgpu::SurfaceError> {
+        // bring world matrices up to date, then leave out everything the camera cannot see
+        self.scene.update_transforms();
+        self.scene.cull(&Frustum::new(&self.camera));
+
+        // build any pipelines needed by objects added since the last frame
+        for key in self.scene.pipeline_keys() {
+            self.pipeline(key);
+        }
+
+        // outline the triangles directly when the adapter can, otherwise fall back to generated edges
+        let outline = self.debug_view.wireframe && self.polygon_mode_line();
+        let edges = self.debug_view.wireframe && !outline;
+        if outline {
+            self.pipeline(PipelineKey::overlay(Topology::TriangleList, wgpu::PolygonMode::Line));
+        }
+
+        // normals and fallback edges join whatever the app drew this frame
+        if self.debug_view.wireframe || self.debug_view.normals {
+            self.scene.debug_lines(&self.debug_view, edges, &mut self.debug_draw);
+        }
+
+        let debug_key = PipelineKey::overlay(Topology::TriangleList, wgpu::PolygonMode::Fill);
+        if !self.debug_draw.is_empty() {
+            self.pipeline(debug_key);
+        }
+        self.debug_draw.upload(&self.device, &self.queue, Facing::camera(&self.camera, self.size.height));
+        self.debug_draw.clear();
+
+        // write this frame's model matrices and instances
+        self.scene.prepare(&self.device, &self.queue, &self.model_bind_group_layout);
+
         let output = self.surface.get_current_texture()?;
         let view = output.texture.create_view(&wgpu::TextureViewDescriptor::default());
         let mut encoder = self.device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
@@ -376,13 +729,31 @@ impl Graphics {
                         },
                     })
                 ],
-                depth_stencil_attachment: None,
+                depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachment {
+                    view: &self.depth_texture.view,
+                    depth_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(Texture::depth_clear_value(self.depth_compare)),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
             });
 
-            render_pass.set_pipeline(&self.render_pipeline); 
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
-            render_pass.set_index_buffer(self.index_buffer.slice(..), wgpu::IndexFormat::Uint16);
-            render_pass.draw_indexed(0..self.n_indices, 0, 0..1);
+            render_pass.set_bind_group(0, &self.camera_bind_group, &[]);
+            render_pass.set_bind_group(1, &self.light_bind_group, &[]);
+            self.scene.draw(&mut render_pass, &self.pipelines);
+
+            if outline {
+                self.scene.draw_wireframe(&mut render_pass, &self.pipelines);
+            }
+
+            // instances and debug shapes are already in world space
+            render_pass.set_bind_group(2, &self.identity_model_bind_group, &[0]);
+            self.scene.draw_instanced(&mut render_pass, &self.pipelines);
+
+            if let Some(pipeline) = self.pipelines.get(&debug_key) {
+                self.debug_draw.draw(&mut render_pass, pipeline);
+            }
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -393,16 +764,25 @@ impl Graphics {
 }
 
 #[cfg_attr(target_arch="wasm32", wasm_bindgen(start))]
-pub async fn run(geometry: Geometry) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn run<A: App + 'static>(mut app: A) -> Result<(), Box<dyn std::error::Error>> {
     let event_loop = EventLoop::new();
     let window = Graphics::new_window(&event_loop);
-    let mut graphics = Graphics::new(window, geometry).await;
+    let mut graphics = Graphics::new(window).await;
+    let mut clock = Clock::default();
+
+    // let the app populate the scene
+    app.init(&mut Context { graphics: &mut graphics, time: clock.time() });
 
     event_loop.run(move |event, _, control_flow| match event {
         Event::WindowEvent {
             ref event,
             window_id,
-        } if window_id == graphics.window.id() => { // UPDATED!
+        } if window_id == graphics.window.id() => {
+            // give the app the first chance to handle the event
+            if app.on_event(&mut Context { graphics: &mut graphics, time: clock.time() }, event) {
+                return;
+            }
+
             match event {
                 WindowEvent::CloseRequested
                 | WindowEvent::KeyboardInput {
@@ -420,6 +800,16 @@ pub async fn run(geometry: Geometry) -> Result<(), Box<dyn std::error::Error>> {
                 WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
                     graphics.resize(**new_inner_size);
                 },
+                // F1 toggles the wireframe, F2 the normals and F3 steps through the debug colorings
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: ElementState::Pressed,
+                            virtual_keycode: Some(key),
+                            ..
+                        },
+                    ..
+                } => graphics.debug_input(*key),
                 WindowEvent::MouseInput { .. } | WindowEvent::CursorMoved { .. } => {
                     graphics.input(event);
                 },
@@ -427,7 +817,18 @@ pub async fn run(geometry: Geometry) -> Result<(), Box<dyn std::error::Error>> {
             }
         },
         Event::RedrawRequested(_) => {
+            clock.tick();
+
+            // run the simulation at a fixed rate, catching up on any time owed
+            while clock.step() {
+                app.fixed_update(&mut Context { graphics: &mut graphics, time: clock.fixed_time() });
+            }
+
+            // update and render once per frame
+            app.update(&mut Context { graphics: &mut graphics, time: clock.time() });
             graphics.update();
+            app.render(&mut Context { graphics: &mut graphics, time: clock.time() });
+
             match graphics.render() {
                 Ok(_) => {},
                 Err(wgpu::SurfaceError::Lost) => graphics.resize(graphics.size),
@@ -441,4 +842,4 @@ pub async fn run(geometry: Geometry) -> Result<(), Box<dyn std::error::Error>> {
         },
         _ => {}
     });
-}
\ No newline at end of file
+}
@@ -132,7 +132,7 @@ impl ShipUI {
         let bottom_left_line = Line::new(points.bottom_left_point, points.center_point, 2.0, 5).mesh;
         let bottom_right_line = Line::new(points.bottom_right_point, points.center_point, 2.0, 5).mesh;
         let mid_right_line = Line::new(points.mid_right_point, points.center_point, 2.0, 5).mesh;
-        let center_circle = Ring::new(points.center_point.position, CIRCLE_RADIUS, 2.0, 15, Color::white()).mesh;
+        let center_circle = Ring::ring(points.center_point.position, CIRCLE_RADIUS, 2.0, 15, Color::white()).mesh;
 
         Self {
             center_circle,
@@ -226,12 +226,3 @@ impl std::fmt::Display for ShipUI {
         )
     }
 }
-
-pub struct Camera {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
-    pub yaw: f32,
-    pub pitch: f32,
-    pub roll: f32,
-}
@@ -0,0 +1,270 @@
+use crate::graphics::{Mesh, Color, Position, Vertex};
+use crate::graphics::{GrowableBuffer, BufferPool, PipelineKey, ModelUniform, Frustum, Mat4};
+use crate::types::{WorldPosition, FloatingOrigin};
+
+// one copy of an instanced mesh, placed by its own transform and tinted by multiplying its vertex colors
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instance {
+    pub transform: Mat4,
+    pub tint: Color,
+    // where in the open world the instance is, its translation is worked out from this whenever the render origin moves
+    // it wins over a translation written straight into the transform, move the instance with set_translation to forget it
+    pub world_position: Option<WorldPosition>,
+}
+
+impl Instance {
+    pub fn new(transform: Mat4, tint: Color) -> Self {
+        Self { transform, tint, world_position: None }
+    }
+
+    // untinted copy moved to a position
+    pub fn at(position: Position) -> Self {
+        Self::new(Mat4::translation(position), Color::white())
+    }
+
+    // untinted copy placed in the open world, drawn relative to the render origin
+    pub fn in_world(position: WorldPosition, origin: &FloatingOrigin) -> Self {
+        Self {
+            world_position: Some(position),
+            ..Self::at(origin.to_render(position))
+        }
+    }
+
+    // move the instance under the render origin, forgetting any world position so the new translation is carried on rebase
+    pub fn set_translation(&mut self, translation: Position) {
+        self.world_position = None;
+        self.transform.set_translation(translation);
+    }
+
+    // place the instance in the open world, drawn relative to the render origin
+    pub fn set_world_position(&mut self, position: WorldPosition, origin: &FloatingOrigin) {
+        self.world_position = Some(position);
+        self.transform.set_translation(origin.to_render(position));
+    }
+
+    // move the translation from under the previous render origin to under the current one
+    pub fn rebase(&mut self, previous: &FloatingOrigin, current: &FloatingOrigin) {
+        let translation = match self.world_position {
+            Some(position) => current.to_render(position),
+            None => current.carry(self.transform.get_translation(), previous),
+        };
+
+        self.transform.set_translation(translation);
+    }
+
+    pub fn to_raw(&self) -> InstanceRaw {
+        let uniform = ModelUniform::new(self.transform);
+
+        InstanceRaw {
+            model: uniform.model.into(),
+            normal: [uniform.normal[0], uniform.normal[1], uniform.normal[2]],
+            tint: self.tint.into(),
+        }
+    }
+}
+
+// instance as it is laid out in the instance buffer
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct InstanceRaw {
+    pub model: [[f32; 4]; 4],
+    pub normal: [[f32; 4]; 3],
+    pub tint: [f32; 4],
+}
+
+impl InstanceRaw {
+    // attributes follow on from the vertex locations, a matrix takes one location per row
+    const ATTRIBUTES: [wgpu::VertexAttribute; 8] = wgpu::vertex_attr_array![
+        3 => Float32x4,
+        4 => Float32x4,
+        5 => Float32x4,
+        6 => Float32x4,
+        7 => Float32x4,
+        8 => Float32x4,
+        9 => Float32x4,
+        10 => Float32x4,
+    ];
+
+    pub fn desc<'a>() -> wgpu::VertexBufferLayout<'a> {
+        wgpu::VertexBufferLayout {
+            array_stride: std::mem::size_of::<InstanceRaw>() as wgpu::BufferAddress,
+            step_mode: wgpu::VertexStepMode::Instance,
+            attributes: &Self::ATTRIBUTES,
+        }
+    }
+}
+
+// handle used to look up an instanced mesh after it has been added to the scene
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct InstancedMeshId(pub u32);
+
+// one mesh drawn many times in a single draw call
+#[derive(Debug)]
+pub struct InstancedMesh {
+    pub id: InstancedMeshId,
+    pub mesh: Mesh,
+    // written to the instance buffer every frame, so they can be changed freely
+    pub instances: Vec<Instance>,
+    pub visible: bool,
+    pub cull_mode: Option<wgpu::Face>,
+    pub vertex_buffer: GrowableBuffer,
+    pub index_buffer: GrowableBuffer,
+    pub instance_buffer: GrowableBuffer,
+    pub index_format: wgpu::IndexFormat,
+    pub n_indices: u32,
+    // whether each instance was in view when last culled
+    in_view: Vec<bool>,
+    n_instances: u32,
+}
+
+impl InstancedMesh {
+    // create an instanced mesh with buffers from the pool and upload its mesh and instances
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, pool: &mut BufferPool, id: InstancedMeshId, mesh: Mesh, instances: Vec<Instance>) -> Self {
+        let vertex_size = (mesh.vertices.len() * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress;
+        let index_size = mesh.index_bytes().len() as wgpu::BufferAddress;
+        let instance_size = (instances.len() * std::mem::size_of::<InstanceRaw>()) as wgpu::BufferAddress;
+        let cull_mode = mesh.topology.default_cull_mode();
+        let index_format = mesh.index_format();
+
+        let mut instanced = Self {
+            id,
+            mesh,
+            instances,
+            visible: true,
+            cull_mode,
+            vertex_buffer: pool.take("Vertex Buffer", wgpu::BufferUsages::VERTEX, vertex_size),
+            index_buffer: pool.take("Index Buffer", wgpu::BufferUsages::INDEX, index_size),
+            instance_buffer: pool.take("Instance Buffer", wgpu::BufferUsages::VERTEX, instance_size),
+            index_format,
+            n_indices: 0,
+            in_view: Vec::new(),
+            n_instances: 0,
+        };
+        instanced.upload(device, queue);
+        instanced.upload_instances(device, queue);
+
+        instanced
+    }
+
+    // write the mesh into the buffers again after its vertices changed
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        self.vertex_buffer.write(device, queue, bytemuck::cast_slice(&self.mesh.vertices));
+        self.index_buffer.write(device, queue, &self.mesh.index_bytes());
+        self.index_format = self.mesh.index_format();
+        self.n_indices = self.mesh.indices.len() as u32;
+    }
+
+    // test every instance against the frustum, returning how many are out of view
+    pub fn cull(&mut self, frustum: Option<&Frustum>) -> u32 {
+        let Some(frustum) = frustum else {
+            self.in_view.clear();
+            return 0;
+        };
+
+        let mesh = &self.mesh;
+        self.in_view = self.instances.iter()
+            .map(|instance| {
+                frustum.intersects_sphere(&mesh.bounding_sphere.transformed(instance.transform))
+                    && frustum.intersects_aabb(&mesh.aabb.transformed(instance.transform))
+            })
+            .collect();
+
+        self.in_view.iter().filter(|in_view| !**in_view).count() as u32
+    }
+
+    // the instances left in view by the last cull
+    pub fn instances_in_view(&self) -> impl Iterator<Item = &Instance> {
+        self.instances.iter()
+            .enumerate()
+            .filter(|(i, _)| self.in_view.get(*i).copied().unwrap_or(true))
+            .map(|(_, instance)| instance)
+    }
+
+    // write the instances left in view by the last cull into the instance buffer
+    pub fn upload_instances(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let raw: Vec<InstanceRaw> = self.instances_in_view()
+            .map(|instance| instance.to_raw())
+            .collect();
+
+        self.instance_buffer.write(device, queue, bytemuck::cast_slice(&raw));
+        self.n_instances = raw.len() as u32;
+    }
+
+    // pipeline the instances are drawn with
+    pub fn pipeline_key(&self) -> PipelineKey {
+        PipelineKey {
+            strip_index_format: if self.mesh.topology.is_strip() { Some(self.index_format) } else { None },
+            instanced: true,
+            ..PipelineKey::new(self.mesh.topology, self.cull_mode)
+        }
+    }
+
+    // draw every instance with one call, leaving the pipeline to the caller
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>) {
+        if !self.visible || self.n_indices == 0 || self.n_instances == 0 {
+            return;
+        }
+
+        let (Some(vertices), Some(indices), Some(instances)) = (self.vertex_buffer.slice(), self.index_buffer.slice(), self.instance_buffer.slice()) else { return };
+
+        render_pass.set_vertex_buffer(0, vertices);
+        render_pass.set_vertex_buffer(1, instances);
+        render_pass.set_index_buffer(indices, self.index_format);
+        render_pass.draw_indexed(0..self.n_indices, 0, 0..self.n_instances);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::Vec3;
+
+    #[test]
+    fn raw_instance_matches_vertex_layout() {
+        // every attribute has to fit inside the stride
+        let layout = InstanceRaw::desc();
+        let end = layout.attributes.iter().map(|attribute| attribute.offset + attribute.format.size()).max().unwrap();
+
+        assert_eq!(end, layout.array_stride);
+        assert_eq!(layout.attributes[0].shader_location, 3);
+    }
+
+    #[test]
+    fn instance_at_translates() {
+        let raw = Instance::at(Position::new(1.0, 2.0, 3.0, 1.0)).to_raw();
+
+        assert_eq!(raw.model[3], [1.0, 2.0, 3.0, 1.0]);
+        assert_eq!(raw.normal[0], [1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(raw.tint, [1.0, 1.0, 1.0, 1.0]);
+    }
+
+    #[test]
+    fn world_instances_are_placed_from_their_world_position() {
+        let far = WorldPosition::new(1.496e11, 2.0, -3.0);
+        let mut origin = FloatingOrigin::default();
+        let mut instance = Instance::in_world(far, &origin);
+
+        // far from the origin the translation is only a rough guess, until the origin comes close
+        let previous = origin;
+        origin.rebase(far + Vec3::new(-10.0, 0.0, 0.0));
+        instance.rebase(&previous, &origin);
+
+        assert_eq!(instance.transform.get_translation(), Position::new(10.0, 0.0, 0.0, 1.0));
+    }
+
+    #[test]
+    fn moved_instances_are_carried_instead_of_placed() {
+        let mut origin = FloatingOrigin::default();
+        let mut instance = Instance::in_world(WorldPosition::new(5.0, 0.0, 0.0), &origin);
+
+        // a translation set after the world position is the one kept on rebase
+        instance.set_translation(Position::new(0.0, 7.0, 0.0, 1.0));
+        assert_eq!(instance.world_position, None);
+
+        let previous = origin;
+        origin.rebase(WorldPosition::new(0.0, 2.0, 0.0));
+        instance.rebase(&previous, &origin);
+
+        assert_eq!(instance.transform.get_translation(), Position::new(0.0, 5.0, 0.0, 1.0));
+    }
+}
@@ -0,0 +1,159 @@
+use crate::graphics::Color;
+use crate::graphics::Position;
+use crate::graphics::Normal;
+
+// maximum number of point lights the shader will evaluate
+pub const MAX_POINT_LIGHTS: usize = 4;
+
+// light shining in a single direction from infinitely far away, like a sun
+#[derive(Debug, Clone, Copy)]
+pub struct DirectionalLight {
+    pub direction: Normal,
+    pub color: Color,
+    pub intensity: f32,
+}
+
+// light radiating from a point and fading out to nothing at its range
+#[derive(Debug, Clone, Copy)]
+pub struct PointLight {
+    pub position: Position,
+    pub color: Color,
+    pub intensity: f32,
+    pub range: f32,
+}
+
+// all of the lights in the scene along with the shared blinn-phong material settings
+#[derive(Debug, Clone)]
+pub struct Lighting {
+    pub enabled: bool,
+    pub ambient: Color,
+    pub ambient_intensity: f32,
+    pub directional: DirectionalLight,
+    pub point_lights: Vec<PointLight>,
+    pub specular_strength: f32,
+    pub shininess: f32,
+}
+
+impl DirectionalLight {
+    pub fn new(direction: Normal, color: Color, intensity: f32) -> Self {
+        Self { direction, color, intensity }
+    }
+}
+
+impl PointLight {
+    pub fn new(position: Position, color: Color, intensity: f32, range: f32) -> Self {
+        Self { position, color, intensity, range }
+    }
+}
+
+impl Lighting {
+    pub fn new(ambient: Color, ambient_intensity: f32, directional: DirectionalLight) -> Self {
+        Self {
+            enabled: true,
+            ambient,
+            ambient_intensity,
+            directional,
+            point_lights: Vec::new(),
+            specular_strength: 0.5,
+            shininess: 32.0,
+        }
+    }
+
+    // lighting that leaves vertex colors untouched
+    pub fn unlit() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+
+    // add a point light, returning false once the shader limit is reached
+    pub fn add_point_light(&mut self, light: PointLight) -> bool {
+        if self.point_lights.len() >= MAX_POINT_LIGHTS {
+            return false;
+        }
+
+        self.point_lights.push(light);
+
+        true
+    }
+
+    // pack the lights into the layout the shader expects
+    pub fn to_uniform(&self) -> LightUniform {
+        let mut points = [PointLightUniform::zeroed(); MAX_POINT_LIGHTS];
+
+        for (uniform, light) in points.iter_mut().zip(&self.point_lights) {
+            *uniform = PointLightUniform {
+                position: [light.position.x, light.position.y, light.position.z, 1.0],
+                color: [light.color.r, light.color.g, light.color.b, light.intensity],
+                attenuation: [light.range, 0.0, 0.0, 0.0],
+            };
+        }
+
+        let direction = self.directional.direction;
+
+        LightUniform {
+            ambient: [self.ambient.r, self.ambient.g, self.ambient.b, self.ambient_intensity],
+            directional: DirectionalLightUniform {
+                direction: [direction[0], direction[1], direction[2], 0.0],
+                color: [self.directional.color.r, self.directional.color.g, self.directional.color.b, self.directional.intensity],
+            },
+            points,
+            material: [self.specular_strength, self.shininess, if self.enabled { 1.0 } else { 0.0 }, 0.0],
+            count: [self.point_lights.len().min(MAX_POINT_LIGHTS) as u32, 0, 0, 0],
+        }
+    }
+}
+
+impl Default for Lighting {
+    // dim white ambient with a white sun shining down and away from the camera
+    fn default() -> Self {
+        Self::new(
+            Color::white(),
+            0.15,
+            DirectionalLight::new(Normal::new(-0.4, -0.8, -0.45), Color::white(), 1.0),
+        )
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct DirectionalLightUniform {
+    pub direction: [f32; 4],
+    // rgb color with the intensity in alpha
+    pub color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct PointLightUniform {
+    pub position: [f32; 4],
+    // rgb color with the intensity in alpha
+    pub color: [f32; 4],
+    // x holds the range
+    pub attenuation: [f32; 4],
+}
+
+impl PointLightUniform {
+    const fn zeroed() -> Self {
+        Self {
+            position: [0.0; 4],
+            color: [0.0; 4],
+            attenuation: [0.0; 4],
+        }
+    }
+}
+
+// lights as laid out in the shader's light uniform
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct LightUniform {
+    // rgb color with the intensity in alpha
+    pub ambient: [f32; 4],
+    pub directional: DirectionalLightUniform,
+    pub points: [PointLightUniform; MAX_POINT_LIGHTS],
+    // specular strength, shininess and whether lighting is enabled
+    pub material: [f32; 4],
+    // x holds the number of active point lights
+    pub count: [u32; 4],
+}
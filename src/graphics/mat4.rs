@@ -0,0 +1,355 @@
+use crate::graphics::position::Position;
+use crate::graphics::normal::Normal;
+use crate::graphics::quat::Quat;
+
+// 4x4 matrix laid out for row vectors (position * matrix), so each row is where a basis vector ends up
+// and a * b applies a before b, uploaded to the shader as is
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Mat4(pub [[f32; 4]; 4]);
+
+impl Mat4 {
+    pub const IDENTITY: Mat4 = Mat4([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    pub fn new(rows: [[f32; 4]; 4]) -> Self {
+        Self(rows)
+    }
+
+    pub fn identity() -> Self {
+        Self::IDENTITY
+    }
+
+    pub fn translation(offset: Position) -> Self {
+        let mut matrix = Self::IDENTITY;
+        matrix.0[3] = [offset.x, offset.y, offset.z, 1.0];
+
+        matrix
+    }
+
+    pub fn scale(x: f32, y: f32, z: f32) -> Self {
+        Self([
+            [x, 0.0, 0.0, 0.0],
+            [0.0, y, 0.0, 0.0],
+            [0.0, 0.0, z, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // counter-clockwise rotation in radians looking down the axis towards the origin
+    pub fn rotation(axis: Normal, angle: f32) -> Self {
+        Self::from_quat(Quat::from_axis_angle(axis, angle))
+    }
+
+    pub fn from_quat(rotation: Quat) -> Self {
+        let Quat { x, y, z, w } = rotation.normalize();
+
+        Self([
+            [1.0 - 2.0 * (y * y + z * z), 2.0 * (x * y + w * z), 2.0 * (x * z - w * y), 0.0],
+            [2.0 * (x * y - w * z), 1.0 - 2.0 * (x * x + z * z), 2.0 * (y * z + w * x), 0.0],
+            [2.0 * (x * z + w * y), 2.0 * (y * z - w * x), 1.0 - 2.0 * (x * x + y * y), 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    // scale, then rotate, then translate
+    pub fn from_scale_rotation_translation(scale: Normal, rotation: Quat, translation: Position) -> Self {
+        let mut matrix = Self::from_quat(rotation);
+
+        for (row, scale) in matrix.0.iter_mut().zip(scale.0) {
+            for value in row.iter_mut().take(3) {
+                *value *= scale;
+            }
+        }
+        matrix.0[3] = [translation.x, translation.y, translation.z, 1.0];
+
+        matrix
+    }
+
+    // world to view transform for a camera at eye looking along its right, up and forward axes
+    pub fn view(eye: Position, right: Normal, up: Normal, forward: Normal) -> Self {
+        let eye = Normal::new(eye.x, eye.y, eye.z);
+
+        Self([
+            [right[0], up[0], -forward[0], 0.0],
+            [right[1], up[1], -forward[1], 0.0],
+            [right[2], up[2], -forward[2], 0.0],
+            [-right.dot(eye), -up.dot(eye), forward.dot(eye), 1.0],
+        ])
+    }
+
+    // world to view transform for a camera at eye looking at target, with -z forward in view space
+    pub fn look_at(eye: Position, target: Position, up: Normal) -> Self {
+        let forward = Normal::new(target.x - eye.x, target.y - eye.y, target.z - eye.z).normalize();
+        let right = forward.cross(up).normalize();
+        let up = right.cross(forward);
+
+        Self::view(eye, right, up, forward)
+    }
+
+    // right handed perspective projection with wgpu's 0..1 depth range
+    pub fn perspective(fovy: f32, aspect: f32, znear: f32, zfar: f32) -> Self {
+        let f = 1.0 / (fovy / 2.0).tan();
+        let range = znear - zfar;
+
+        Self([
+            [f / aspect, 0.0, 0.0, 0.0],
+            [0.0, f, 0.0, 0.0],
+            [0.0, 0.0, zfar / range, -1.0],
+            [0.0, 0.0, znear * zfar / range, 0.0],
+        ])
+    }
+
+    // right handed orthographic projection with wgpu's 0..1 depth range
+    pub fn orthographic(left: f32, right: f32, bottom: f32, top: f32, znear: f32, zfar: f32) -> Self {
+        let width = right - left;
+        let height = top - bottom;
+        let range = znear - zfar;
+
+        Self([
+            [2.0 / width, 0.0, 0.0, 0.0],
+            [0.0, 2.0 / height, 0.0, 0.0],
+            [0.0, 0.0, 1.0 / range, 0.0],
+            [-(right + left) / width, -(top + bottom) / height, znear / range, 1.0],
+        ])
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut matrix = Self([[0.0; 4]; 4]);
+
+        for (i, row) in self.0.iter().enumerate() {
+            for (j, value) in row.iter().enumerate() {
+                matrix.0[j][i] = *value;
+            }
+        }
+
+        matrix
+    }
+
+    // the 2x2 determinants of the top and bottom halves, shared by the determinant and the inverse
+    fn minors(&self) -> ([f32; 6], [f32; 6]) {
+        let a = &self.0;
+
+        let s = [
+            a[0][0] * a[1][1] - a[1][0] * a[0][1],
+            a[0][0] * a[1][2] - a[1][0] * a[0][2],
+            a[0][0] * a[1][3] - a[1][0] * a[0][3],
+            a[0][1] * a[1][2] - a[1][1] * a[0][2],
+            a[0][1] * a[1][3] - a[1][1] * a[0][3],
+            a[0][2] * a[1][3] - a[1][2] * a[0][3],
+        ];
+        let c = [
+            a[2][0] * a[3][1] - a[3][0] * a[2][1],
+            a[2][0] * a[3][2] - a[3][0] * a[2][2],
+            a[2][0] * a[3][3] - a[3][0] * a[2][3],
+            a[2][1] * a[3][2] - a[3][1] * a[2][2],
+            a[2][1] * a[3][3] - a[3][1] * a[2][3],
+            a[2][2] * a[3][3] - a[3][2] * a[2][3],
+        ];
+
+        (s, c)
+    }
+
+    pub fn determinant(&self) -> f32 {
+        let (s, c) = self.minors();
+
+        s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0]
+    }
+
+    // inverse transform, or none when the matrix flattens space
+    pub fn inverse(&self) -> Option<Self> {
+        let a = &self.0;
+        let (s, c) = self.minors();
+
+        let determinant = s[0] * c[5] - s[1] * c[4] + s[2] * c[3] + s[3] * c[2] - s[4] * c[1] + s[5] * c[0];
+        if determinant.abs() <= f32::EPSILON * f32::EPSILON {
+            return None;
+        }
+        let inv = 1.0 / determinant;
+
+        Some(Self([
+            [
+                (a[1][1] * c[5] - a[1][2] * c[4] + a[1][3] * c[3]) * inv,
+                (-a[0][1] * c[5] + a[0][2] * c[4] - a[0][3] * c[3]) * inv,
+                (a[3][1] * s[5] - a[3][2] * s[4] + a[3][3] * s[3]) * inv,
+                (-a[2][1] * s[5] + a[2][2] * s[4] - a[2][3] * s[3]) * inv,
+            ],
+            [
+                (-a[1][0] * c[5] + a[1][2] * c[2] - a[1][3] * c[1]) * inv,
+                (a[0][0] * c[5] - a[0][2] * c[2] + a[0][3] * c[1]) * inv,
+                (-a[3][0] * s[5] + a[3][2] * s[2] - a[3][3] * s[1]) * inv,
+                (a[2][0] * s[5] - a[2][2] * s[2] + a[2][3] * s[1]) * inv,
+            ],
+            [
+                (a[1][0] * c[4] - a[1][1] * c[2] + a[1][3] * c[0]) * inv,
+                (-a[0][0] * c[4] + a[0][1] * c[2] - a[0][3] * c[0]) * inv,
+                (a[3][0] * s[4] - a[3][1] * s[2] + a[3][3] * s[0]) * inv,
+                (-a[2][0] * s[4] + a[2][1] * s[2] - a[2][3] * s[0]) * inv,
+            ],
+            [
+                (-a[1][0] * c[3] + a[1][1] * c[1] - a[1][2] * c[0]) * inv,
+                (a[0][0] * c[3] - a[0][1] * c[1] + a[0][2] * c[0]) * inv,
+                (-a[3][0] * s[3] + a[3][1] * s[1] - a[3][2] * s[0]) * inv,
+                (a[2][0] * s[3] - a[2][1] * s[1] + a[2][2] * s[0]) * inv,
+            ],
+        ]))
+    }
+
+    // inverse transpose of the upper 3x3, up to scale, so normals stay perpendicular under non-uniform scale
+    pub fn normal_matrix(&self) -> Self {
+        let rows = [0, 1, 2].map(|i| Normal::new(self.0[i][0], self.0[i][1], self.0[i][2]));
+        let cofactors = [rows[1].cross(rows[2]), rows[2].cross(rows[0]), rows[0].cross(rows[1])];
+
+        // mirroring transforms turn the cofactors inside out
+        let sign = rows[0].dot(cofactors[0]).signum();
+
+        let mut normal = Self::IDENTITY;
+        for (row, cofactor) in normal.0.iter_mut().zip(cofactors) {
+            *row = [cofactor[0] * sign, cofactor[1] * sign, cofactor[2] * sign, 0.0];
+        }
+
+        normal
+    }
+
+    pub fn get_translation(&self) -> Position {
+        Position::new(self.0[3][0], self.0[3][1], self.0[3][2], 1.0)
+    }
+
+    pub fn set_translation(&mut self, translation: Position) {
+        self.0[3] = [translation.x, translation.y, translation.z, 1.0];
+    }
+
+    // transform a point, picking up the translation
+    pub fn transform_point(&self, point: Position) -> Position {
+        Position::new(point.x, point.y, point.z, 1.0) * *self
+    }
+
+    // transform a direction, ignoring the translation
+    pub fn transform_vector(&self, vector: Normal) -> Normal {
+        Normal::from(vector.to_vec4() * *self)
+    }
+}
+
+impl Default for Mat4 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+// compose two transforms, applying self first
+impl std::ops::Mul for Mat4 {
+    type Output = Mat4;
+
+    fn mul(self, rhs: Mat4) -> Mat4 {
+        let mut result = [[0.0; 4]; 4];
+
+        for (i, row) in result.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                *value = (0..4).map(|k| self.0[i][k] * rhs.0[k][j]).sum();
+            }
+        }
+
+        Mat4(result)
+    }
+}
+
+impl std::ops::Mul<Mat4> for Position {
+    type Output = Position;
+
+    fn mul(self, rhs: Mat4) -> Position {
+        self * rhs.0
+    }
+}
+
+// implement indexing a row of the matrix
+impl std::ops::Index<usize> for Mat4 {
+    type Output = [f32; 4];
+
+    fn index(&self, index: usize) -> &Self::Output {
+        &self.0[index]
+    }
+}
+
+impl std::ops::IndexMut<usize> for Mat4 {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        &mut self.0[index]
+    }
+}
+
+impl From<[[f32; 4]; 4]> for Mat4 {
+    fn from(rows: [[f32; 4]; 4]) -> Self {
+        Self(rows)
+    }
+}
+
+impl From<Mat4> for [[f32; 4]; 4] {
+    fn from(matrix: Mat4) -> Self {
+        matrix.0
+    }
+}
+
+impl From<Quat> for Mat4 {
+    fn from(rotation: Quat) -> Self {
+        Self::from_quat(rotation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Mat4, b: Mat4) -> bool {
+        a.0.iter().flatten().zip(b.0.iter().flatten()).all(|(a, b)| (a - b).abs() < 1e-4)
+    }
+
+    #[test]
+    fn inverse_undoes_the_transform() {
+        let matrix = Mat4::from_scale_rotation_translation(
+            Normal::new(2.0, 0.5, 3.0),
+            Quat::from_euler(0.3, -1.1, 2.0),
+            Position::new(4.0, -5.0, 6.0, 1.0),
+        ) * Mat4::new([
+            [1.0, 2.0, 3.0, 4.0],
+            [0.0, 1.0, 5.0, 2.0],
+            [2.0, 0.0, 1.0, 3.0],
+            [1.0, 1.0, 0.0, 1.0],
+        ]);
+
+        let inverse = matrix.inverse().unwrap();
+        assert!(close(matrix * inverse, Mat4::IDENTITY));
+        assert!(close(inverse * matrix, Mat4::IDENTITY));
+        assert!((matrix.determinant() * inverse.determinant() - 1.0).abs() < 1e-4);
+
+        assert!(Mat4::scale(1.0, 0.0, 1.0).inverse().is_none());
+    }
+
+    #[test]
+    fn rotation_turns_counter_clockwise() {
+        let rotation = Mat4::rotation(Normal::new(0.0, 0.0, 1.0), std::f32::consts::FRAC_PI_2);
+        let turned = rotation.transform_vector(Normal::new(1.0, 0.0, 0.0));
+
+        assert!((turned[0]).abs() < 1e-6 && (turned[1] - 1.0).abs() < 1e-6);
+
+        // a rotation is undone by its transpose
+        assert!(close(rotation * rotation.transpose(), Mat4::IDENTITY));
+    }
+
+    #[test]
+    fn look_at_puts_the_target_in_front() {
+        let view = Mat4::look_at(Position::new(1.0, 2.0, 3.0, 1.0), Position::new(-2.0, 0.0, 1.0, 1.0), Normal::new(0.0, 1.0, 0.0));
+        let target = view.transform_point(Position::new(-2.0, 0.0, 1.0, 1.0));
+
+        // straight ahead down -z
+        assert!(target.x.abs() < 1e-5 && target.y.abs() < 1e-5 && target.z < 0.0);
+
+        let projection = Mat4::orthographic(-1.0, 1.0, -1.0, 1.0, 1.0, 11.0);
+        let near = Position::new(1.0, 1.0, -1.0, 1.0) * projection;
+        let far = Position::new(-1.0, -1.0, -11.0, 1.0) * projection;
+        assert!(close(Mat4([[near.x, near.y, near.z, near.w], [far.x, far.y, far.z, far.w], [0.0; 4], [0.0; 4]]),
+            Mat4([[1.0, 1.0, 0.0, 1.0], [-1.0, -1.0, 1.0, 1.0], [0.0; 4], [0.0; 4]])));
+    }
+}
@@ -1,16 +1,150 @@
 use crate::graphics::vertex::Vertex;
 use crate::graphics::position::Position;
+use std::collections::{HashMap, HashSet};
 use crate::graphics::normal::Normal;
+use crate::graphics::bounds::{Aabb, BoundingSphere};
+use crate::graphics::vec3::Vec3;
+use crate::graphics::quat::Quat;
+use crate::graphics::SubdivisionError;
+
+// how vertex normals are derived from the faces around them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NormalMode {
+    // every triangle gets its own vertices carrying the face normal
+    Flat,
+    // shared vertices average the faces around them weighted by face area
+    AreaWeighted,
+    // shared vertices average the faces around them weighted by the corner angle
+    AngleWeighted,
+}
+
+// how the indices of a mesh are assembled into primitives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Topology {
+    PointList,
+    LineList,
+    LineStrip,
+    TriangleList,
+    TriangleStrip,
+}
+
+impl Topology {
+    pub fn to_wgpu(self) -> wgpu::PrimitiveTopology {
+        match self {
+            Topology::PointList => wgpu::PrimitiveTopology::PointList,
+            Topology::LineList => wgpu::PrimitiveTopology::LineList,
+            Topology::LineStrip => wgpu::PrimitiveTopology::LineStrip,
+            Topology::TriangleList => wgpu::PrimitiveTopology::TriangleList,
+            Topology::TriangleStrip => wgpu::PrimitiveTopology::TriangleStrip,
+        }
+    }
+
+    pub fn is_strip(self) -> bool {
+        matches!(self, Topology::LineStrip | Topology::TriangleStrip)
+    }
+
+    // only triangles have a back face to cull
+    pub fn default_cull_mode(self) -> Option<wgpu::Face> {
+        match self {
+            Topology::TriangleList | Topology::TriangleStrip => Some(wgpu::Face::Back),
+            _ => None,
+        }
+    }
+}
+
+// index that ends one strip and starts the next
+pub const STRIP_RESTART: u32 = u32::MAX;
 
 #[derive(Debug, Clone)]
 pub struct Mesh {
     pub vertices: Vec<Vertex>,
-    pub indices: Vec<u16>,
+    pub indices: Vec<u32>,
+    pub topology: Topology,
+    // kept in step by translate and rotate, call update_bounds after moving vertices directly
+    pub aabb: Aabb,
+    pub bounding_sphere: BoundingSphere,
 }
 
 impl Mesh {
-    pub fn new(vertices: Vec<Vertex>, indices: Vec<u16>) -> Self {
-        Self { vertices, indices }
+    pub fn new(vertices: Vec<Vertex>, indices: Vec<u32>) -> Self {
+        Self::with_topology(vertices, indices, Topology::TriangleList)
+    }
+
+    pub fn with_topology(vertices: Vec<Vertex>, indices: Vec<u32>, topology: Topology) -> Self {
+        let positions: Vec<Position> = vertices.iter().map(|vertex| vertex.position).collect();
+
+        Self {
+            vertices,
+            indices,
+            topology,
+            aabb: Aabb::from_points(positions.iter().copied()),
+            bounding_sphere: BoundingSphere::from_points(&positions),
+        }
+    }
+
+    // recalculate the bounding volumes from the vertices
+    pub fn update_bounds(&mut self) {
+        let positions: Vec<Position> = self.vertices.iter().map(|vertex| vertex.position).collect();
+
+        self.aabb = Aabb::from_points(positions.iter().copied());
+        self.bounding_sphere = BoundingSphere::from_points(&positions);
+    }
+
+    // index of the vertex after the first count, panicking rather than silently wrapping
+    pub fn index_of(count: usize) -> u32 {
+        match u32::try_from(count) {
+            Ok(index) if index != STRIP_RESTART => index,
+            _ => panic!("mesh has more vertices than a u32 index can address"),
+        }
+    }
+
+    // every triangle of the mesh, unrolling strips and leaving out points and lines
+    pub fn triangles(&self) -> impl Iterator<Item = [u32; 3]> + '_ {
+        let (list, strip) = (self.topology == Topology::TriangleList, self.topology == Topology::TriangleStrip);
+
+        let listed = self.indices.chunks_exact(3)
+            .filter(move |_| list)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]]);
+
+        // every other triangle of a strip is flipped to keep the winding, and repeated indices only stitch strips together
+        let unrolled = self.indices.split(|index| *index == STRIP_RESTART)
+            .filter(move |_| strip)
+            .flat_map(|strip| strip.windows(3).enumerate())
+            .map(|(k, window)| if k % 2 == 0 { [window[0], window[1], window[2]] } else { [window[1], window[0], window[2]] })
+            .filter(|[a, b, c]| a != b && b != c && a != c);
+
+        listed.chain(unrolled)
+    }
+
+    // indices of every triangle as a triangle list
+    pub fn triangle_list(&self) -> Vec<u32> {
+        self.triangles().flatten().collect()
+    }
+
+    // narrowest index format that can address every vertex, keeping the maximum free to restart strips
+    pub fn index_format(&self) -> wgpu::IndexFormat {
+        if self.vertices.len() < u16::MAX as usize {
+            wgpu::IndexFormat::Uint16
+        } else {
+            wgpu::IndexFormat::Uint32
+        }
+    }
+
+    // indices packed in the index format, padded to the four byte alignment of buffer copies
+    pub fn index_bytes(&self) -> Vec<u8> {
+        match self.index_format() {
+            wgpu::IndexFormat::Uint16 => {
+                let mut indices: Vec<u16> = self.indices.iter()
+                    .map(|index| if *index == STRIP_RESTART { u16::MAX } else { *index as u16 })
+                    .collect();
+                if indices.len() % 2 == 1 {
+                    indices.push(0);
+                }
+
+                bytemuck::cast_slice(&indices).to_vec()
+            }
+            wgpu::IndexFormat::Uint32 => bytemuck::cast_slice(&self.indices).to_vec(),
+        }
     }
 
     // length of distance between start and end point
@@ -18,11 +152,9 @@ impl Mesh {
         (end - start).sqrt()
     }
 
-    // direction of the line from start to end point
-    pub fn vertex_direction(start: Position, end: Position) -> Position {
-        let length = Mesh::vertex_length(start, end);
-
-        end - start / length
+    // unit direction of the line from start to end point
+    pub fn vertex_direction(start: Position, end: Position) -> Vec3 {
+        end.direction(start)
     }
 
     // dot product of two vectors
@@ -35,23 +167,175 @@ impl Mesh {
         a.cross(b)
     }
 
-    // calculate normal of a triangle surface based on three points
+    // calculate the unit normal of a counter-clockwise triangle based on three points
     pub fn normalize(a: Position, b: Position, c: Position) -> Normal {
-        (b - a).cross(a - c).into()
+        Normal::from((b - a).cross(c - a)).normalize()
+    }
+
+    // recalculate every vertex normal from the triangles of the mesh
+    pub fn compute_normals(&mut self, mode: NormalMode) {
+        match mode {
+            NormalMode::Flat => self.flat_normals(),
+            NormalMode::AreaWeighted | NormalMode::AngleWeighted => self.smooth_normals(mode),
+        }
+    }
+
+    // split the mesh so no vertex is shared between triangles and give each the face normal
+    fn flat_normals(&mut self) {
+        let mut vertices: Vec<Vertex> = Vec::with_capacity(self.indices.len());
+        let mut indices: Vec<u32> = Vec::with_capacity(self.indices.len());
+
+        for triangle in self.indices.chunks_exact(3) {
+            let a = self.vertices[triangle[0] as usize];
+            let b = self.vertices[triangle[1] as usize];
+            let c = self.vertices[triangle[2] as usize];
+            let normal = Mesh::normalize(a.position, b.position, c.position);
+
+            for vertex in [a, b, c] {
+                indices.push(Mesh::index_of(vertices.len()));
+                vertices.push(Vertex::new(vertex.position, vertex.color, normal));
+            }
+        }
+
+        self.vertices = vertices;
+        self.indices = indices;
+    }
+
+    // accumulate weighted face normals onto each shared vertex
+    fn smooth_normals(&mut self, mode: NormalMode) {
+        let mut normals = vec![Normal::new(0.0, 0.0, 0.0); self.vertices.len()];
+
+        for triangle in self.indices.chunks_exact(3) {
+            let corners = [triangle[0] as usize, triangle[1] as usize, triangle[2] as usize];
+            let a = self.vertices[corners[0]].position;
+            let b = self.vertices[corners[1]].position;
+            let c = self.vertices[corners[2]].position;
+
+            // the length of the cross product is twice the area of the triangle
+            let face: Normal = (b - a).cross(c - a).into();
+            let unit = face.normalize();
+
+            for (i, corner) in corners.iter().enumerate() {
+                let weighted = match mode {
+                    NormalMode::AngleWeighted => {
+                        let point = self.vertices[*corner].position;
+                        let next = self.vertices[corners[(i + 1) % 3]].position;
+                        let previous = self.vertices[corners[(i + 2) % 3]].position;
+
+                        unit * Mesh::corner_angle(point, next, previous)
+                    },
+                    _ => face,
+                };
+
+                normals[*corner] = normals[*corner] + weighted;
+            }
+        }
+
+        for (vertex, normal) in self.vertices.iter_mut().zip(normals) {
+            vertex.normal = normal.normalize();
+        }
+    }
+
+    // angle at point between the edges leading to next and previous
+    fn corner_angle(point: Position, next: Position, previous: Position) -> f32 {
+        let u: Normal = (next - point).into();
+        let v: Normal = (previous - point).into();
+        let lengths = u.length() * v.length();
+
+        if lengths == 0.0 {
+            return 0.0;
+        }
+
+        (u.dot(v) / lengths).clamp(-1.0, 1.0).acos()
+    }
+
+    // check that splitting every triangle that many times still leaves every vertex addressable by a u32 index
+    pub fn subdivision_fits(&self, iterations: u32) -> Result<(), SubdivisionError> {
+        let triangles = &self.indices[..self.indices.len() - self.indices.len() % 3];
+        let edges: HashSet<(u32, u32)> = triangles.chunks_exact(3)
+            .flat_map(|triangle| [(triangle[0], triangle[1]), (triangle[1], triangle[2]), (triangle[2], triangle[0])])
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect();
+
+        // every level adds a vertex per edge, splits each edge in two and adds three edges inside each face
+        let (mut vertices, mut edges, mut faces) = (self.vertices.len() as u64, edges.len() as u64, triangles.len() as u64 / 3);
+        for _ in 0..iterations {
+            vertices += edges;
+            edges = edges * 2 + faces * 3;
+            faces *= 4;
+
+            // the last index has to stay below the strip restart
+            if vertices > STRIP_RESTART as u64 {
+                return Err(SubdivisionError::TooManyVertices { level: iterations });
+            }
+        }
+
+        Ok(())
+    }
+
+    // split every triangle into four by its edge midpoints, leaving the mesh untouched when the result would not fit
+    pub fn subdivide(&mut self, iterations: u32) -> Result<(), SubdivisionError> {
+        self.subdivision_fits(iterations)?;
+
+        for _ in 0..iterations {
+            // midpoints are cached per edge so neighbouring triangles share them
+            let mut midpoints: HashMap<(u32, u32), u32> = HashMap::new();
+            let mut indices: Vec<u32> = Vec::with_capacity(self.indices.len() * 4);
+
+            for i in (0..self.indices.len() - self.indices.len() % 3).step_by(3) {
+                let a = self.indices[i];
+                let b = self.indices[i + 1];
+                let c = self.indices[i + 2];
+
+                let ab = self.midpoint(&mut midpoints, a, b);
+                let bc = self.midpoint(&mut midpoints, b, c);
+                let ca = self.midpoint(&mut midpoints, c, a);
+
+                // keep the winding of the original triangle
+                indices.extend_from_slice(&[
+                    a, ab, ca,
+                    b, bc, ab,
+                    c, ca, bc,
+                    ab, bc, ca,
+                ]);
+            }
+
+            self.indices = indices;
+        }
+
+        Ok(())
+    }
+
+    // find or create the vertex halfway along an edge
+    fn midpoint(&mut self, midpoints: &mut HashMap<(u32, u32), u32>, a: u32, b: u32) -> u32 {
+        let key = (a.min(b), a.max(b));
+
+        if let Some(index) = midpoints.get(&key) {
+            return *index;
+        }
+
+        let mut vertex = self.vertices[a as usize].interpolate(self.vertices[b as usize], 0.5);
+        vertex.normal = vertex.normal.normalize();
+
+        let index = Mesh::index_of(self.vertices.len());
+        self.vertices.push(vertex);
+        midpoints.insert(key, index);
+
+        index
     }
 
     // deduplicate vertices
     pub fn dedup(&mut self) {
         let mut vertices: Vec<Vertex> = Vec::new();
-        let mut indices: Vec<u16> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
 
         for index in &self.indices {
             let vertex = self.vertices[*index as usize];
 
             if let Some(i) = vertices.iter().position(|v| *v == vertex) {
-                indices.push(i as u16);
+                indices.push(i as u32);
             } else {
-                indices.push(vertices.len() as u16);
+                indices.push(Mesh::index_of(vertices.len()));
                 vertices.push(vertex);
             }
         }
@@ -60,11 +344,35 @@ impl Mesh {
         self.indices = indices;
     }
 
+    // average position of all vertices
+    pub fn centroid(&self) -> Position {
+        if self.vertices.is_empty() {
+            return Position::new(0.0, 0.0, 0.0, 1.0);
+        }
+
+        let sum = self.vertices.iter()
+            .fold(Position::new(0.0, 0.0, 0.0, 0.0), |sum, vertex| sum + vertex.position);
+
+        sum / self.vertices.len() as f32
+    }
+
     // rotate mesh around an axis
-    pub fn rotate(&mut self, axis: Position, origin: Position, angle: f32) {
+    pub fn rotate(&mut self, axis: Vec3, origin: Position, angle: f32) {
+        self.rotate_by(Quat::from_axis_angle(axis.into(), angle), origin);
+    }
+
+    // rotate mesh by a quaternion around an origin
+    pub fn rotate_by(&mut self, rotation: Quat, origin: Position) {
+        // normals are directions so they only turn, positions also swing around the origin
         for vertex in &mut self.vertices {
-            vertex.position = vertex.position.rotate(angle, origin,  axis);
+            let offset = rotation.rotate_position(vertex.position - origin);
+
+            vertex.position = Position::new(offset.x + origin.x, offset.y + origin.y, offset.z + origin.z, vertex.position.w);
+            vertex.normal = rotation.rotate(vertex.normal);
         }
+
+        // a rotated box no longer fits the mesh, so both are fitted again
+        self.update_bounds();
     }
 
     // translate mesh
@@ -72,6 +380,9 @@ impl Mesh {
         for vertex in &mut self.vertices {
             vertex.position += position;
         }
+
+        self.aabb.translate(position);
+        self.bounding_sphere.translate(position);
     }
 }
 
@@ -83,12 +394,19 @@ impl std::ops::Add for Mesh {
         let mut vertices = self.vertices;
         let mut indices = self.indices;
 
-        let offset = vertices.len() as u16;
+        let offset = Mesh::index_of(vertices.len());
+        // the merged mesh has to stay addressable
+        Mesh::index_of(vertices.len() + other.vertices.len());
+
+        // strips would otherwise be joined end to end
+        if self.topology.is_strip() && !indices.is_empty() && !other.indices.is_empty() {
+            indices.push(STRIP_RESTART);
+        }
 
         vertices.extend(other.vertices);
-        indices.extend(other.indices.iter().map(|i| i + offset));
+        indices.extend(other.indices.iter().map(|i| if *i == STRIP_RESTART { *i } else { i + offset }));
 
-        Self { vertices, indices }
+        Self::with_topology(vertices, indices, self.topology)
     }
 }
 
@@ -112,4 +430,31 @@ impl std::fmt::Display for Mesh {
         }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{Triangle, Spherical};
+
+    #[test]
+    fn test_large_meshes_switch_to_u32_indices() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+
+        // small meshes pack their indices into u16, padded to four bytes
+        let triangle = Triangle::triangle(origin, 1.0).mesh;
+        assert_eq!(triangle.index_format(), wgpu::IndexFormat::Uint16);
+        assert_eq!(triangle.index_bytes().len(), 8);
+
+        let sphere = Spherical::uv_sphere(1.0, origin, 400, 200);
+        assert!(sphere.vertices.len() > u16::MAX as usize);
+        assert_eq!(sphere.index_format(), wgpu::IndexFormat::Uint32);
+        assert_eq!(sphere.index_bytes().len(), sphere.indices.len() * 4);
+
+        // merging offsets the second mesh past every vertex of the first
+        let merged = sphere.clone() + sphere.clone();
+        assert_eq!(merged.vertices.len(), sphere.vertices.len() * 2);
+        assert!(merged.indices.iter().all(|index| (*index as usize) < merged.vertices.len()));
+        assert_eq!(*merged.indices.last().unwrap() as usize, *sphere.indices.last().unwrap() as usize + sphere.vertices.len());
+    }
+}
@@ -26,6 +26,27 @@ impl Normal {
         ])
     }
 
+    // find the dot product of two normals
+    pub fn dot(self, target: Self) -> f32 {
+        self.0[0] * target.0[0] + self.0[1] * target.0[1] + self.0[2] * target.0[2]
+    }
+
+    // length of the normal
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    // scale the normal to unit length, leaving a zero normal untouched
+    pub fn normalize(self) -> Normal {
+        let length = self.length();
+
+        if length == 0.0 {
+            return self;
+        }
+
+        self / length
+    }
+
     // interpolate
     pub fn interpolate(self, target: Self, t: f32) -> Normal {
         self * (1.0 - t) + target * t
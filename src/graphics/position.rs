@@ -1,5 +1,6 @@
-
 use crate::graphics::normal::Normal;
+use crate::graphics::vec3::Vec3;
+use crate::graphics::quat::Quat;
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
@@ -15,28 +16,36 @@ impl Position {
         Self { x, y, z, w }
     }
 
+    // a point, which translations move
+    pub fn point(x: f32, y: f32, z: f32) -> Self {
+        Self::new(x, y, z, 1.0)
+    }
+
     // get the distance between two positions
     pub fn distance(self, target: Position) -> f32 {
-        let d = self - target;
+        self.offset_to(target).length()
+    }
 
-        (d.x * d.x + d.y * d.y + d.z * d.z).sqrt()
+    // the offset that takes this position to the target
+    pub fn offset_to(self, target: Position) -> Vec3 {
+        Vec3::new(target.x - self.x, target.y - self.y, target.z - self.z)
     }
-    
-    // get the direction to a target position
-    pub fn direction(self, target: Position) -> Position {
-        (self - target) / self.distance(target)
+
+    // get the unit direction from a target position back to this one
+    pub fn direction(self, target: Position) -> Vec3 {
+        target.offset_to(self).normalize()
     }
 
-    // get the magnitutde of a position
+    // get the length of x, y and z, ignoring w
     pub fn magnitude(self) -> f32 {
-        self.distance(self)
+        Vec3::from(self).length()
     }
 
     pub fn sqrt(self) -> f32 {
-        (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
+        self.magnitude()
     }
 
-    // noramlize a position
+    // scale x, y and z to unit length, keeping w
     pub fn normalize(self) -> Position {
         let magnitude = self.magnitude();
 
@@ -44,64 +53,32 @@ impl Position {
             return self;
         }
 
-        self / magnitude
+        Self::new(self.x / magnitude, self.y / magnitude, self.z / magnitude, self.w)
     }
 
-    // cross product
+    // cross product of x, y and z, giving a direction with a w of zero
     pub fn cross(self, target: Position) -> Position {
-        Position::new(
-            self.y * target.z - self.z * target.y,
-            self.z * target.x - self.x * target.z,
-            self.x * target.y - self.y * target.x,
-            self.w * target.w - self.w * target.w,
-        )
+        Vec3::from(self).cross(Vec3::from(target)).into()
     }
 
-    // dot product
+    // dot product of x, y and z, ignoring w
     pub fn dot(self, target: Position) -> f32 {
-        self.x * target.x + self.y * target.y + self.z * target.z + self.w * target.w
+        Vec3::from(self).dot(Vec3::from(target))
     }
 
     // find the next position in a given direction
-    pub fn find_next(self, direction: Position, distance: f32) -> Position {
+    pub fn find_next(self, direction: Vec3, distance: f32) -> Position {
         self + direction * distance
     }
 
-    // rotate a position around the origin across an axis
-    pub fn rotate(&self, angle: f32, origin: Position, axis: Position) -> Position {
-        // trnaslate the position to the origin
-        let position = *self - origin;
-
-        // normalize the axis
-        let axis = axis.normalize();
-
-        // create rotation matrix
-        let rotation_matrix = [
-            [
-                axis.x * axis.x * (1.0 - angle.cos()) + angle.cos(),
-                axis.x * axis.y * (1.0 - angle.cos()) - axis.z * angle.sin(),
-                axis.x * axis.z * (1.0 - angle.cos()) + axis.y * angle.sin(),
-                0.0,
-            ],
-            [
-                axis.y * axis.x * (1.0 - angle.cos()) + axis.z * angle.sin(),
-                axis.y * axis.y * (1.0 - angle.cos()) + angle.cos(),
-                axis.y * axis.z * (1.0 - angle.cos()) - axis.x * angle.sin(),
-                0.0,
-            ],
-            [
-                axis.z * axis.x * (1.0 - angle.cos()) - axis.y * angle.sin(),
-                axis.z * axis.y * (1.0 - angle.cos()) + axis.x * angle.sin(),
-                axis.z * axis.z * (1.0 - angle.cos()) + angle.cos(),
-                0.0,
-            ],
-            [0.0, 0.0, 0.0, 1.0],
-        ];
-
-        // apply rotation matrix to position and translate back
-        let position = position * rotation_matrix;
-
-        position + origin
+    // rotate a position counter-clockwise in radians around an axis through the origin, looking down the axis
+    pub fn rotate(&self, angle: f32, origin: Position, axis: Vec3) -> Position {
+        let rotation = Quat::from_axis_angle(axis.into(), angle);
+
+        // positions keep their w, directions rotate around the origin unmoved
+        let offset = rotation.rotate_position(*self - origin);
+
+        Self::new(offset.x + origin.x, offset.y + origin.y, offset.z + origin.z, self.w)
     }
 
     // interpolate
@@ -327,6 +304,23 @@ impl std::ops::Mul<Normal> for Position {
     }
 }
 
+// move a position by an offset, keeping its w
+impl std::ops::Add<Vec3> for Position {
+    type Output = Self;
+
+    fn add(self, rhs: Vec3) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z, self.w)
+    }
+}
+
+impl std::ops::Sub<Vec3> for Position {
+    type Output = Self;
+
+    fn sub(self, rhs: Vec3) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z, self.w)
+    }
+}
+
 // implement negative for position
 impl std::ops::Neg for Position {
     type Output = Self;
@@ -356,51 +350,154 @@ impl std::fmt::Display for Position {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::f32::consts::{FRAC_PI_2, PI};
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+    use crate::graphics::{Mat4, Mesh};
+
+    const CASES: usize = 500;
+
+    fn close(a: Position, b: Position) -> bool {
+        a.distance(b) < 1e-4 && (a.w - b.w).abs() < 1e-6
+    }
+
+    fn random_vec3(rng: &mut StdRng) -> Vec3 {
+        Vec3::new(rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0), rng.gen_range(-10.0..10.0))
+    }
+
+    fn random_point(rng: &mut StdRng) -> Position {
+        random_vec3(rng).to_point()
+    }
 
     // test rotating 90 degrees around the z axis
     #[test]
     fn test_rotate() {
         let position = Position::new(1.0, 0.0, 0.0, 1.0);
         let origin = Position::new(0.0, 0.0, 0.0, 1.0);
-        let axis = Position::new(0.0, 0.0, 1.0, 1.0);
-
-        let rotated = position.rotate(90.0, origin, axis);
+        let axis = Vec3::Z;
 
-        println!("rotated: {}", rotated);
+        let rotated = position.rotate(FRAC_PI_2, origin, axis);
 
-        assert_eq!(rotated.x, 0.0);
-        assert_eq!(rotated.y, 1.0);
-        assert_eq!(rotated.z, 0.0);
-        assert_eq!(rotated.w, 1.0);
+        assert!(close(rotated, Position::new(0.0, 1.0, 0.0, 1.0)), "rotated: {}", rotated);
     }
 
-    // test rotation 45 degrees around the x and y axis
+    // test rotation 180 degrees around the x and y axis
     #[test]
     fn test_rotate_2() {
         let position = Position::new(1.0, 0.0, 0.0, 1.0);
         let origin = Position::new(0.0, 0.0, 0.0, 1.0);
-        let axis = Position::new(1.0, 1.0, 0.0, 1.0);
+        let axis = Vec3::new(1.0, 1.0, 0.0);
 
-        let rotated = position.rotate(std::f32::consts::PI / 4.0, origin, axis);
+        let rotated = position.rotate(PI, origin, axis);
 
-        assert_eq!(rotated.x, 0.0);
-        assert_eq!(rotated.y, 0.0);
-        assert_eq!(rotated.z, 1.0);
-        assert_eq!(rotated.w, 1.0);
+        assert!(close(rotated, Position::new(0.0, 1.0, 0.0, 1.0)), "rotated: {}", rotated);
     }
 
-    // test rotation 180 degreez around x, y, z
+    // test rotation 180 degrees around x, y, z, turning a perpendicular point to the other side and leaving the axis alone
     #[test]
     fn test_rotate_3() {
-        let position = Position::new(1.0, 1.0, 1.0, 1.0);
+        let position = Position::new(1.0, -1.0, 0.0, 1.0);
         let origin = Position::new(0.0, 0.0, 0.0, 1.0);
-        let axis = Position::new(1.0, 1.0, 1.0, 1.0);
+        let axis = Vec3::new(1.0, 1.0, 1.0);
+
+        let rotated = position.rotate(PI, origin, axis);
+        assert!(close(rotated, Position::new(-1.0, 1.0, 0.0, 1.0)), "rotated: {}", rotated);
+
+        let on_axis = Position::new(1.0, 1.0, 1.0, 1.0);
+        assert!(close(on_axis.rotate(PI, origin, axis), on_axis));
+    }
+
+    #[test]
+    fn magnitude_and_normalize_ignore_w() {
+        let position = Position::new(3.0, 0.0, 4.0, 1.0);
 
-        let rotated = position.rotate(std::f32::consts::PI, origin, axis);
+        assert_eq!(position.magnitude(), 5.0);
+        assert_eq!(position.normalize(), Position::new(0.6, 0.0, 0.8, 1.0));
+        assert_eq!(position.dot(Position::new(1.0, 1.0, 1.0, 1.0)), 7.0);
+        assert_eq!(position.cross(Position::new(0.0, 1.0, 0.0, 1.0)).w, 0.0);
+    }
 
-        assert_eq!(rotated.x, -1.0);
-        assert_eq!(rotated.y, -1.0);
-        assert_eq!(rotated.z, -1.0);
-        assert_eq!(rotated.w, 1.0);
+    #[test]
+    fn vector_identities_hold() {
+        let mut rng = StdRng::seed_from_u64(23);
+
+        for _ in 0..CASES {
+            let (a, b, c) = (random_vec3(&mut rng), random_vec3(&mut rng), random_vec3(&mut rng));
+            let scale = a.length() * b.length();
+
+            // the cross product is perpendicular to both sides and anticommutes
+            assert!(a.cross(b).dot(a).abs() <= 1e-4 * scale * a.length());
+            assert!(a.cross(b).dot(b).abs() <= 1e-4 * scale * b.length());
+            assert!((a.cross(b) + b.cross(a)).length() <= 1e-4 * scale);
+
+            // lagrange's identity ties the two products to the lengths
+            let lagrange = a.cross(b).length_squared() + a.dot(b).powi(2);
+            assert!((lagrange - scale * scale).abs() <= 1e-4 * scale * scale);
+
+            // the scalar triple product is the same in any cyclic order
+            let triple = a.dot(b.cross(c));
+            let bound = 1e-4 * scale * c.length();
+            assert!((triple - b.dot(c.cross(a))).abs() <= bound);
+            assert!((triple - c.dot(a.cross(b))).abs() <= bound);
+
+            assert_eq!(a.dot(b), b.dot(a));
+            if a.length() > 1e-3 {
+                assert!((a.normalize().length() - 1.0).abs() < 1e-5);
+            }
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn points_and_directions_keep_their_w() {
+        let mut rng = StdRng::seed_from_u64(7);
+
+        for _ in 0..CASES {
+            let (p, q, v) = (random_point(&mut rng), random_point(&mut rng), random_vec3(&mut rng));
+
+            // a point minus a point is a direction, a point plus a direction is a point
+            assert_eq!((p - q).w, 0.0);
+            assert_eq!((p + v).w, 1.0);
+            assert_eq!(Position::from(v).w, 0.0);
+            assert!(close(p + p.offset_to(q), q));
+
+            // a translation moves points but not directions
+            let translation = Mat4::translation(random_point(&mut rng));
+            assert!(close(Position::from(v) * translation, Position::from(v)));
+            assert!((p.distance(q) - (p * translation).distance(q * translation)).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn rotations_preserve_lengths_and_undo() {
+        let mut rng = StdRng::seed_from_u64(42);
+
+        for _ in 0..CASES {
+            let (point, origin) = (random_point(&mut rng), random_point(&mut rng));
+            let axis = random_vec3(&mut rng);
+            if axis.length() < 1e-3 {
+                continue;
+            }
+            let angle = rng.gen_range(-PI..PI);
+
+            let rotated = point.rotate(angle, origin, axis);
+
+            // the distance to the origin and the height along the axis stay the same
+            assert!((rotated.distance(origin) - point.distance(origin)).abs() < 1e-3);
+            let height = |p: Position| origin.offset_to(p).dot(axis.normalize());
+            assert!((height(rotated) - height(point)).abs() < 1e-3);
+
+            // turning back by the same angle returns to the start, and the matrix agrees
+            assert!((rotated.rotate(-angle, origin, axis).distance(point)) < 1e-3);
+            let matrix = Mat4::translation(-origin) * Mat4::rotation(axis.into(), angle) * Mat4::translation(origin);
+            assert!((point * matrix).distance(rotated) < 1e-3);
+        }
+    }
+
+    // direction points from the target back to self, and vertex_direction from start to end
+    #[test]
+    fn test_direction_orientation() {
+        let (a, b) = (Position::point(1.0, 2.0, 3.0), Position::point(1.0, 2.0, 7.0));
+
+        assert!((a.direction(b) - Vec3::new(0.0, 0.0, -1.0)).length() < 1e-6);
+        assert!((Mesh::vertex_direction(a, b) - Vec3::Z).length() < 1e-6);
+    }
+}
@@ -4,8 +4,8 @@ use crate::graphics::Vertex;
 use crate::graphics::Position;
 use crate::graphics::Color;
 use crate::graphics::Normal;
-use crate::graphics::Square;
 use crate::graphics::Geometry;
+use crate::graphics::SubdivisionError;
 
 #[derive(Debug, Clone)]
 pub struct Cube {
@@ -84,70 +84,32 @@ impl Cube {
             Color::blue(), 
             Normal::new(0.0, 0.0, 1.0));
 
-        // add vertices and indices
-        vertices.push(flb);             // black - 0
-        vertices.push(flt);             // cyan - 1
-        vertices.push(frb);             // yellow - 2
-        vertices.push(frt);             // magenta - 3
-        vertices.push(brb);             // red - 4
-        vertices.push(brt);             // blue - 5
-        vertices.push(blb);             // white - 6 
-        vertices.push(blt);             // green - 7
-
-        // wind the indices in clockwise
-        // front face         // 012  132
-        indices.push(0);
-        indices.push(1);
-        indices.push(2);
-
-        indices.push(1);
-        indices.push(3);
-        indices.push(2);
-
-        // back face        // 456 576
-        indices.push(4);
-        indices.push(5);
-        indices.push(6);
-
-        indices.push(5);
-        indices.push(7);
-        indices.push(6);
-
-        // left face          // 670 710
-        indices.push(6);
-        indices.push(7);
-        indices.push(0);
-
-        indices.push(7);
-        indices.push(1);
-        indices.push(0);
-
-        // right face        // 234 354
-        indices.push(2);
-        indices.push(3);
-        indices.push(4);
-
-        indices.push(3);
-        indices.push(5);
-        indices.push(4);
-
-        // top face        // 173 753
-        indices.push(1);
-        indices.push(7);
-        indices.push(3);
-
-        indices.push(7);
-        indices.push(5);
-        indices.push(3);
-
-        // bottom face     // 604 024
-        indices.push(6);
-        indices.push(0);
-        indices.push(4);
-
-        indices.push(0);
-        indices.push(2);
-        indices.push(4);
+        // each face gets its own four corners so it can carry its own normal
+        // corners are listed in the order Square expects, wound counter-clockwise from outside
+        let faces = [
+            [flb, flt, frb, frt],       // front
+            [brb, brt, blb, blt],       // back
+            [blb, blt, flb, flt],       // left
+            [frb, frt, brb, brt],       // right
+            [flt, blt, frt, brt],       // top
+            [blb, flb, brb, frb],       // bottom
+        ];
+
+        for face in faces {
+            let offset = Mesh::index_of(vertices.len());
+            let normal = Mesh::normalize(face[0].position, face[1].position, face[2].position);
+
+            // add the four corners of the face with the face normal
+            for corner in face {
+                vertices.push(Vertex::new(corner.position, corner.color, normal));
+            }
+
+            // two triangles per face   // 012  132
+            indices.extend_from_slice(&[
+                offset, offset + 1, offset + 2,
+                offset + 1, offset + 3, offset + 2,
+            ]);
+        }
 
         Mesh::new(vertices, indices)
     }
@@ -165,48 +127,9 @@ impl Cube {
     //     self.mesh.scale(scale);
     // }
 
-    // subdivide cube surfaces
-    pub fn subdivide(&mut self, n_subdivisions: u32) {
-        // create new vertex and index vectors
-        let mut vertices: Vec<Vertex> = vec![];
-        let mut indices: Vec<u16> = vec![];
-
-        // get all 8 corners of the cube
-        let flb = self.mesh.vertices[0];
-        let flt = self.mesh.vertices[1];
-        let frb = self.mesh.vertices[2];
-        let frt = self.mesh.vertices[3];
-        let brb = self.mesh.vertices[4];
-        let brt = self.mesh.vertices[5];
-        let blb = self.mesh.vertices[6];
-        let blt = self.mesh.vertices[7];
-        
-        // create a square for each side
-        let mut squares: Vec<Square> = vec![];
-        squares.push(Square::from_vertices(vec![flb, flt, frb, frt]));
-        squares.push(Square::from_vertices(vec![brb, brt, blb, blt]));
-        squares.push(Square::from_vertices(vec![blb, blt, flb, flt]));
-        squares.push(Square::from_vertices(vec![frb, frt, brb, brt]));
-        squares.push(Square::from_vertices(vec![flt, blt, frt, brt]));
-        squares.push(Square::from_vertices(vec![blb, flb, brb, frb]));
-
-        // subdivide each square
-        for square in &mut squares {
-            square.subdivide(n_subdivisions);
-        }
-
-        // add vertices and indices to the cube
-        for square in squares {
-            for index in square.mesh.indices {
-                indices.push(vertices.len() as u16 + index);
-            }
-            for vertex in square.mesh.vertices {
-                vertices.push(vertex);
-            }
-        }
-
-        // update mesh
-        self.mesh = Mesh::new(vertices, indices);
+    // subdivide cube surfaces, splitting every triangle of the current mesh so repeated calls and earlier edits carry over
+    pub fn subdivide(&mut self, n_subdivisions: u32) -> Result<(), SubdivisionError> {
+        self.mesh.subdivide(n_subdivisions)
     }
 }
 
@@ -221,4 +144,36 @@ impl std::fmt::Display for Cube {
                             indices: {} }} 
                             ", self.origin, self.size, self.mesh.vertices, self.mesh.indices.len())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subdivide_twice_keeps_the_faces() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let mut cube = Cube::cube(origin, 2.0);
+        cube.subdivide(1).unwrap();
+        cube.subdivide(1).unwrap();
+
+        // each face is a 5 by 5 grid of its own vertices split into 32 triangles
+        assert_eq!(cube.mesh.vertices.len(), 6 * 25);
+        assert_eq!(cube.mesh.indices.len(), 6 * 32 * 3);
+
+        for triangle in cube.mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| cube.mesh.vertices[triangle[k] as usize]);
+
+            // all three corners lie on the same face and share its outward normal
+            let normal = Mesh::normalize(a.position, b.position, c.position);
+            let face = Normal::from(a.position);
+            assert!(normal.dot(face) > 0.0);
+            assert!([a, b, c].iter().all(|vertex| (vertex.normal - normal).length() < 1e-5));
+
+            for vertex in [a, b, c] {
+                let p = vertex.position;
+                assert!((p.x.abs().max(p.y.abs()).max(p.z.abs()) - 1.0).abs() < 1e-6);
+            }
+        }
+    }
+}
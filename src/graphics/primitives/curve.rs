@@ -0,0 +1,189 @@
+use crate::graphics::position::Position;
+use crate::graphics::color::Color;
+use crate::graphics::mesh::Mesh;
+use crate::graphics::camera::Camera;
+use crate::graphics::{Line, LineStyle, Facing};
+
+// default number of line segments each span of a curve is split into
+pub const CURVE_SUBDIVISION: u32 = 16;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Spline {
+    // spans of three control points, sharing their end points
+    QuadraticBezier,
+    // spans of four control points, sharing their end points
+    CubicBezier,
+    // passes through every control point
+    CatmullRom,
+}
+
+impl Spline {
+    pub fn build(self, control_points: Vec<Position>, start_color: Color, end_color: Color, style: LineStyle, facing: Facing) -> Curve {
+        Curve::new(self, control_points, start_color, end_color, style, facing, CURVE_SUBDIVISION)
+    }
+}
+
+// smooth curve tessellated into a thick line
+pub struct Curve {
+    pub spline_type: Spline,
+    pub control_points: Vec<Position>,
+    pub start_color: Color,
+    pub end_color: Color,
+    pub style: LineStyle,
+    pub facing: Facing,
+    pub subdivision: u32,
+    pub mesh: Mesh,
+}
+
+impl Curve {
+    pub fn new(spline_type: Spline, control_points: Vec<Position>, start_color: Color, end_color: Color, style: LineStyle, facing: Facing, subdivision: u32) -> Self {
+        let mut curve = Self {
+            spline_type,
+            control_points,
+            start_color,
+            end_color,
+            style,
+            facing,
+            subdivision,
+            mesh: Mesh::new(Vec::new(), Vec::new()),
+        };
+        curve.generate();
+
+        curve
+    }
+
+    // points along the curve, with the given number of segments per span
+    pub fn points(&self) -> Vec<Position> {
+        let segments = self.subdivision.max(1);
+        let control = &self.control_points;
+        let mut points: Vec<Position> = Vec::new();
+
+        // evaluate each span, skipping its first point when it repeats the end of the previous span
+        let mut span = |evaluate: &dyn Fn(f32) -> Position| {
+            let first = if points.is_empty() { 0 } else { 1 };
+            points.extend((first..=segments).map(|i| evaluate(i as f32 / segments as f32)));
+        };
+
+        match self.spline_type {
+            Spline::QuadraticBezier => {
+                for i in (0..control.len().saturating_sub(2)).step_by(2) {
+                    span(&|t| quadratic_bezier(control[i], control[i + 1], control[i + 2], t));
+                }
+            }
+            Spline::CubicBezier => {
+                for i in (0..control.len().saturating_sub(3)).step_by(3) {
+                    span(&|t| cubic_bezier(control[i], control[i + 1], control[i + 2], control[i + 3], t));
+                }
+            }
+            Spline::CatmullRom => {
+                // the end points are repeated so the curve reaches them
+                let last = control.len().saturating_sub(1);
+                for i in 0..last {
+                    let p0 = control[i.saturating_sub(1)];
+                    let p3 = control[(i + 2).min(last)];
+                    span(&|t| catmull_rom(p0, control[i], control[i + 1], p3, t));
+                }
+            }
+        }
+
+        points
+    }
+
+    // change the number of segments per span
+    pub fn subdivide(&mut self, subdivision: u32) {
+        self.subdivision = subdivision;
+        self.generate();
+    }
+
+    // rebuild the mesh so it faces the camera
+    pub fn face_camera(&mut self, camera: &Camera, screen_height: u32) {
+        self.facing = Facing::camera(camera, screen_height);
+        self.generate();
+    }
+
+    fn generate(&mut self) {
+        self.mesh = Line::extrude(&self.points(), self.start_color, self.end_color, self.style, self.facing);
+    }
+}
+
+// point on a quadratic bezier curve
+pub fn quadratic_bezier(p0: Position, p1: Position, p2: Position, t: f32) -> Position {
+    let s = 1.0 - t;
+
+    p0 * (s * s) + p1 * (2.0 * s * t) + p2 * (t * t)
+}
+
+// point on a cubic bezier curve
+pub fn cubic_bezier(p0: Position, p1: Position, p2: Position, p3: Position, t: f32) -> Position {
+    let s = 1.0 - t;
+
+    p0 * (s * s * s) + p1 * (3.0 * s * s * t) + p2 * (3.0 * s * t * t) + p3 * (t * t * t)
+}
+
+// point on a uniform catmull-rom span between p1 and p2
+pub fn catmull_rom(p0: Position, p1: Position, p2: Position, p3: Position, t: f32) -> Position {
+    let t2 = t * t;
+    let t3 = t2 * t;
+
+    p0 * (-0.5 * t3 + t2 - 0.5 * t)
+        + p1 * (1.5 * t3 - 2.5 * t2 + 1.0)
+        + p2 * (-1.5 * t3 + 2.0 * t2 + 0.5 * t)
+        + p3 * (0.5 * t3 - 0.5 * t2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{Normal, LineWidth, LineCap, LineJoin};
+
+    fn curve(spline_type: Spline, control_points: Vec<Position>) -> Curve {
+        let style = LineStyle::new(LineWidth::World(0.1), LineCap::Butt, LineJoin::Miter);
+        let facing = Facing::Fixed(Normal::new(0.0, 0.0, 1.0));
+
+        Curve::new(spline_type, control_points, Color::white(), Color::white(), style, facing, 8)
+    }
+
+    #[test]
+    fn test_bezier_spans_share_end_points() {
+        let control = vec![
+            Position::new(0.0, 0.0, 0.0, 1.0),
+            Position::new(1.0, 2.0, 0.0, 1.0),
+            Position::new(2.0, 0.0, 0.0, 1.0),
+            Position::new(3.0, -2.0, 0.0, 1.0),
+            Position::new(4.0, 0.0, 0.0, 1.0),
+        ];
+
+        let quadratic = curve(Spline::QuadraticBezier, control.clone());
+        let points = quadratic.points();
+        assert_eq!(points.len(), 2 * 8 + 1);
+        assert_eq!(points[0], control[0]);
+        assert_eq!(points[8], control[2]);
+        assert_eq!(points[16], control[4]);
+        assert!(points[4].distance(Position::new(1.0, 1.0, 0.0, 1.0)) < 1e-5);
+        assert!(!quadratic.mesh.indices.is_empty());
+
+        // the fifth point is left over without a full cubic span
+        let cubic = curve(Spline::CubicBezier, control.clone());
+        let points = cubic.points();
+        assert_eq!(points.len(), 8 + 1);
+        assert_eq!(points[8], control[3]);
+        assert!(points[4].distance(cubic_bezier(control[0], control[1], control[2], control[3], 0.5)) < 1e-5);
+    }
+
+    #[test]
+    fn test_catmull_rom_passes_through_control_points() {
+        let control = vec![
+            Position::new(0.0, 0.0, 0.0, 1.0),
+            Position::new(1.0, 1.0, 0.0, 1.0),
+            Position::new(2.0, 0.0, 0.0, 1.0),
+            Position::new(3.0, 1.0, 0.0, 1.0),
+        ];
+
+        let points = curve(Spline::CatmullRom, control.clone()).points();
+        assert_eq!(points.len(), 3 * 8 + 1);
+
+        for (i, point) in control.iter().enumerate() {
+            assert!(points[i * 8].distance(*point) < 1e-5);
+        }
+    }
+}
@@ -1,4 +1,33 @@
-use crate::graphics::{Spherical, Sphere, Cube, Triangle, Square, Vertex, Position, Mesh};
+use crate::graphics::{Spherical, Sphere, Cube, Triangle, Square, Ring, Vertex, Position, Vec3, Mesh, Color, RING_SUBDIVISION};
+
+// why a shape could not be subdivided as far as asked
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubdivisionError {
+    // doubling the resolution that many times no longer fits in a u32
+    ResolutionOverflow { resolution: u32, level: u32 },
+    // the subdivided mesh would have more vertices than a u32 index can address
+    TooManyVertices { level: u32 },
+}
+
+impl SubdivisionError {
+    // the resolution doubled once per level, or an error once it stops fitting
+    pub fn doubled(resolution: u32, level: u32) -> Result<u32, SubdivisionError> {
+        resolution.checked_shl(level)
+            .filter(|doubled| doubled >> level == resolution)
+            .ok_or(SubdivisionError::ResolutionOverflow { resolution, level })
+    }
+}
+
+impl std::fmt::Display for SubdivisionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::ResolutionOverflow { resolution, level } => write!(f, "doubling a resolution of {} {} times overflows a u32", resolution, level),
+            Self::TooManyVertices { level } => write!(f, "subdividing {} times needs more vertices than a u32 index can address", level),
+        }
+    }
+}
+
+impl std::error::Error for SubdivisionError {}
 
 pub enum Shape {
     Triangle,
@@ -7,6 +36,7 @@ pub enum Shape {
     UVSphere,
     Icosahedron,
     SpherifiedCube,
+    Ring,
 }
 
 #[derive(Debug, Clone)]
@@ -15,6 +45,7 @@ pub enum Geometry {
     Cube(Cube),
     Square(Square),
     Sphere(Spherical),
+    Ring(Ring),
 }
 
 impl Geometry {
@@ -26,6 +57,7 @@ impl Geometry {
             Shape::UVSphere => Self::Sphere(Spherical::sphere(size, origin, Sphere::UVSphere)),
             Shape::Icosahedron => Self::Sphere(Spherical::sphere(size, origin, Sphere::Icosahedron)),
             Shape::SpherifiedCube => Self::Sphere(Spherical::sphere(size, origin, Sphere::SpherifiedCube)),
+            Shape::Ring => Self::Ring(<Ring as Geometric>::new(origin, size)),
         }
     }
 
@@ -35,6 +67,17 @@ impl Geometry {
             Self::Cube(cube) => cube.mesh(),
             Self::Square(square) => square.mesh(),
             Self::Sphere(sphere) => sphere.mesh(),
+            Self::Ring(ring) => ring.mesh(),
+        }
+    }
+
+    pub fn origin(&self) -> Position {
+        match self {
+            Self::Triangle(triangle) => triangle.origin(),
+            Self::Cube(cube) => cube.origin(),
+            Self::Square(square) => square.origin(),
+            Self::Sphere(sphere) => sphere.origin(),
+            Self::Ring(ring) => ring.origin(),
         }
     }
 
@@ -44,15 +87,17 @@ impl Geometry {
             Self::Cube(cube) => cube.vertices(),
             Self::Square(square) => square.vertices(),
             Self::Sphere(sphere) => sphere.vertices(),
+            Self::Ring(ring) => ring.vertices(),
         }
     }
 
-    pub fn indices(&self) -> &Vec<u16> {
+    pub fn indices(&self) -> &Vec<u32> {
         match self {
             Self::Triangle(triangle) => triangle.indices(),
             Self::Cube(cube) => cube.indices(),
             Self::Square(square) => square.indices(),
             Self::Sphere(sphere) => sphere.indices(),
+            Self::Ring(ring) => ring.indices(),
         }
     }
 
@@ -62,6 +107,7 @@ impl Geometry {
             Self::Cube(cube) => cube.vertex_len(),
             Self::Square(square) => square.vertex_len(),
             Self::Sphere(sphere) => sphere.vertex_len(),
+            Self::Ring(ring) => ring.vertex_len(),
         }
     }
 
@@ -71,25 +117,30 @@ impl Geometry {
             Self::Cube(cube) => cube.index_len(),
             Self::Square(square) => square.index_len(),
             Self::Sphere(sphere) => sphere.index_len(),
+            Self::Ring(ring) => ring.index_len(),
         }
     }
 
-    pub fn rotate(&mut self, angle: f32, axis: Position) {
+    pub fn rotate(&mut self, angle: f32, axis: Vec3) {
         match self {
             Self::Triangle(triangle) => triangle.rotate(angle, axis),
             Self::Cube(cube) => cube.rotate(angle, axis),
             Self::Square(square) => square.rotate(angle, axis),
             Self::Sphere(sphere) => sphere.rotate(angle, axis),
+            Self::Ring(ring) => ring.rotate(angle, axis),
         }
     }
 
-    pub fn subdivide(&mut self, level: u32) {
+    pub fn subdivide(&mut self, level: u32) -> Result<(), SubdivisionError> {
         match self {
-            Self::Triangle(triangle) => triangle.subdivide(level),
-            Self::Cube(cube) => cube.subdivide(level),
-            Self::Square(square) => square.subdivide(level),
-            Self::Sphere(sphere) => sphere.subdivide(level),
+            Self::Triangle(triangle) => triangle.subdivide(level)?,
+            Self::Cube(cube) => cube.subdivide(level)?,
+            Self::Square(square) => square.subdivide(level)?,
+            Self::Sphere(sphere) => sphere.subdivide(level)?,
+            Self::Ring(ring) => ring.subdivide(level)?,
         }
+
+        Ok(())
     }
 
     pub fn dedup(&mut self) {
@@ -98,6 +149,7 @@ impl Geometry {
             Self::Cube(cube) => cube.dedup(),
             Self::Square(square) => square.dedup(),
             Self::Sphere(sphere) => sphere.dedup(),
+            Self::Ring(ring) => ring.dedup(),
         }
     }
 }
@@ -106,54 +158,166 @@ impl Geometry {
 pub trait Geometric {
     fn new(origin: Position, size: f32) -> Self;
     fn mesh(&self) -> &Mesh;
+    fn origin(&self) -> Position;
     fn vertices(&self) -> &Vec<Vertex>;
-    fn indices(&self) -> &Vec<u16>;
+    fn indices(&self) -> &Vec<u32>;
     fn vertex_len(&self) -> usize;
     fn index_len(&self) -> usize;
-    fn rotate(&mut self, angle: f32, axis: Position);
+    fn rotate(&mut self, angle: f32, axis: Vec3);
     fn dedup(&mut self);
 }
 
 impl Geometric for Triangle {
     fn new(origin: Position, size: f32) -> Self { Self::triangle(origin, size) }
     fn mesh(&self) -> &Mesh { &self.mesh }
+    fn origin(&self) -> Position { self.origin }
     fn vertices(&self) -> &Vec<Vertex> { &self.mesh.vertices }
-    fn indices(&self) -> &Vec<u16> { &self.mesh.indices }
+    fn indices(&self) -> &Vec<u32> { &self.mesh.indices }
     fn vertex_len(&self) -> usize { self.mesh.vertices.len() }
     fn index_len(&self) -> usize { self.mesh.indices.len() }
-    fn rotate(&mut self, angle: f32, axis: Position) { self.mesh.rotate(axis, self.origin, angle); }
+    fn rotate(&mut self, angle: f32, axis: Vec3) { self.mesh.rotate(axis, self.origin, angle); }
     fn dedup(&mut self) { self.mesh.dedup(); }
 }
 
 impl Geometric for Cube {
     fn new(origin: Position, size: f32) -> Self { Self::cube(origin, size) }
     fn mesh(&self) -> &Mesh { &self.mesh }
+    fn origin(&self) -> Position { self.origin }
     fn vertices(&self) -> &Vec<Vertex> { &self.mesh.vertices }
-    fn indices(&self) -> &Vec<u16> { &self.mesh.indices }
+    fn indices(&self) -> &Vec<u32> { &self.mesh.indices }
     fn vertex_len(&self) -> usize { self.mesh.vertices.len() }
     fn index_len(&self) -> usize { self.mesh.indices.len() }
-    fn rotate(&mut self, angle: f32, axis: Position) { self.mesh.rotate(axis, self.origin, angle); }
+    fn rotate(&mut self, angle: f32, axis: Vec3) { self.mesh.rotate(axis, self.origin, angle); }
     fn dedup(&mut self) { self.mesh.dedup(); }
 }
 
 impl Geometric for Square {
     fn new(origin: Position, size: f32) -> Self { Self::quad(origin, size) }
     fn mesh(&self) -> &Mesh { &self.mesh }
+    fn origin(&self) -> Position { self.origin }
     fn vertices(&self) -> &Vec<Vertex> { &self.mesh.vertices }
-    fn indices(&self) -> &Vec<u16> { &self.mesh.indices }
+    fn indices(&self) -> &Vec<u32> { &self.mesh.indices }
     fn vertex_len(&self) -> usize { self.mesh.vertices.len() }
     fn index_len(&self) -> usize { self.mesh.indices.len() }
-    fn rotate(&mut self, angle: f32, axis: Position) { self.mesh.rotate(axis, self.origin, angle); }
+    fn rotate(&mut self, angle: f32, axis: Vec3) { self.mesh.rotate(axis, self.origin, angle); }
     fn dedup(&mut self) { self.mesh.dedup(); }
 }
 
 impl Geometric for Spherical {
     fn new(origin: Position, radius: f32) -> Self { Self::sphere(radius, origin, Sphere::UVSphere) }
     fn mesh(&self) -> &Mesh { &self.mesh }
+    fn origin(&self) -> Position { self.origin }
+    fn vertices(&self) -> &Vec<Vertex> { &self.mesh.vertices }
+    fn indices(&self) -> &Vec<u32> { &self.mesh.indices }
+    fn vertex_len(&self) -> usize { self.mesh.vertices.len() }
+    fn index_len(&self) -> usize { self.mesh.indices.len() }
+    fn rotate(&mut self, angle: f32, axis: Vec3) { Spherical::rotate(self, angle, axis); }
+    fn dedup(&mut self) { self.mesh.dedup(); }
+}
+
+impl Geometric for Ring {
+    fn new(origin: Position, radius: f32) -> Self { Self::ring(origin, radius, radius * 0.1, RING_SUBDIVISION, Color::white()) }
+    fn mesh(&self) -> &Mesh { &self.mesh }
+    fn origin(&self) -> Position { self.center }
     fn vertices(&self) -> &Vec<Vertex> { &self.mesh.vertices }
-    fn indices(&self) -> &Vec<u16> { &self.mesh.indices }
+    fn indices(&self) -> &Vec<u32> { &self.mesh.indices }
     fn vertex_len(&self) -> usize { self.mesh.vertices.len() }
     fn index_len(&self) -> usize { self.mesh.indices.len() }
-    fn rotate(&mut self, angle: f32, axis: Position) { self.mesh.rotate(axis, self.origin, angle); }
+    fn rotate(&mut self, angle: f32, axis: Vec3) { Ring::rotate(self, angle, axis); }
     fn dedup(&mut self) { self.mesh.dedup(); }
-}
\ No newline at end of file
+}
+
+// check the normals generated for the closed primitives
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::graphics::NormalMode;
+
+    const CLOSED_SHAPES: [Shape; 4] = [Shape::Cube, Shape::UVSphere, Shape::Icosahedron, Shape::SpherifiedCube];
+
+    // every normal should be unit length and point away from the origin of the shape
+    pub(crate) fn assert_outward_unit_normals(mesh: &Mesh, origin: Position) {
+        for vertex in &mesh.vertices {
+            let normal = vertex.normal;
+            let outward = vertex.position - origin;
+
+            assert!((normal.length() - 1.0).abs() < 1e-4, "normal {} is not unit length", normal);
+            assert!(
+                normal[0] * outward.x + normal[1] * outward.y + normal[2] * outward.z > 0.0,
+                "normal {} at {} points inward", normal, vertex.position
+            );
+        }
+    }
+
+    // every triangle should be wound counter-clockwise when seen from outside
+    pub(crate) fn assert_outward_winding(mesh: &Mesh, origin: Position) {
+        for triangle in mesh.indices.chunks_exact(3) {
+            let a = mesh.vertices[triangle[0] as usize].position;
+            let b = mesh.vertices[triangle[1] as usize].position;
+            let c = mesh.vertices[triangle[2] as usize].position;
+
+            let normal = Mesh::normalize(a, b, c);
+            let outward = (a + b + c) / 3.0 - origin;
+
+            assert!(
+                normal[0] * outward.x + normal[1] * outward.y + normal[2] * outward.z > 0.0,
+                "triangle {:?} is wound inward", triangle
+            );
+        }
+    }
+
+    #[test]
+    fn test_generated_normals_are_unit_and_outward() {
+        let origin = Position::new(0.5, -0.25, 1.0, 1.0);
+
+        for shape in CLOSED_SHAPES {
+            let geometry = Geometry::new(origin, 1.0, shape);
+
+            assert_outward_winding(geometry.mesh(), origin);
+            assert_outward_unit_normals(geometry.mesh(), origin);
+        }
+    }
+
+    #[test]
+    fn test_subdivided_cube_normals_are_unit_and_outward() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let mut cube = Geometry::new(origin, 2.0, Shape::Cube);
+        cube.subdivide(2).unwrap();
+
+        assert_outward_winding(cube.mesh(), origin);
+        assert_outward_unit_normals(cube.mesh(), origin);
+    }
+
+    #[test]
+    fn test_computed_normals_are_unit_and_outward() {
+        let origin = Position::new(0.0, 1.0, -2.0, 1.0);
+
+        for mode in [NormalMode::Flat, NormalMode::AreaWeighted, NormalMode::AngleWeighted] {
+            let mut cube = Geometry::new(origin, 1.0, Shape::Cube).mesh().clone();
+            cube.compute_normals(mode);
+            assert_outward_unit_normals(&cube, origin);
+
+            let mut icosahedron = Geometry::new(origin, 1.0, Shape::Icosahedron).mesh().clone();
+            icosahedron.compute_normals(mode);
+            assert_outward_unit_normals(&icosahedron, origin);
+        }
+    }
+
+    #[test]
+    fn test_flat_normals_match_faces() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let mut icosahedron = Geometry::new(origin, 1.0, Shape::Icosahedron).mesh().clone();
+        icosahedron.compute_normals(NormalMode::Flat);
+
+        assert_eq!(icosahedron.vertices.len(), icosahedron.indices.len());
+
+        for triangle in icosahedron.indices.chunks_exact(3) {
+            let a = icosahedron.vertices[triangle[0] as usize];
+            let b = icosahedron.vertices[triangle[1] as usize];
+            let c = icosahedron.vertices[triangle[2] as usize];
+
+            assert_eq!(a.normal, b.normal);
+            assert_eq!(b.normal, c.normal);
+        }
+    }
+}
@@ -1,61 +1,492 @@
-
+use std::f32::consts::PI;
 use crate::graphics::vertex::Vertex;
 use crate::graphics::position::Position;
+use crate::graphics::normal::Normal;
 use crate::graphics::color::Color;
 use crate::graphics::mesh::Mesh;
+use crate::graphics::camera::Camera;
+
+// triangles used to fan out each round cap
+const ROUND_CAP_SEGMENTS: u32 = 8;
+
+// longest a miter may grow, relative to the half width, before it is bevelled instead
+const MITER_LIMIT: f32 = 4.0;
+
+// how wide a line is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineWidth {
+    // fixed width in world units
+    World(f32),
+    // width on screen, scaled with the distance to the camera
+    Pixels(f32),
+}
+
+// shape added past the first and last points
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineCap {
+    Butt,
+    Square,
+    Round,
+}
+
+// shape used where two segments of a line meet
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineJoin {
+    Miter,
+    Bevel,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineStyle {
+    pub width: LineWidth,
+    pub cap: LineCap,
+    pub join: LineJoin,
+}
+
+// direction the extruded quads face
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Facing {
+    // a fixed direction, where a pixel is one world unit as in the hud
+    Fixed(Normal),
+    // towards the camera, where a pixel covers more of the world the further away it is
+    Camera { position: Position, pixel_size: f32 },
+}
 
 pub struct Line {
     pub start_position: Position,
     pub end_position: Position,
     pub start_color: Color,
     pub end_color: Color,
-    pub thickness: f32,
+    pub style: LineStyle,
+    pub facing: Facing,
     pub subdivision: u32,
     pub mesh: Mesh,
 }
 
+impl LineWidth {
+    // width in world units given the size of a pixel at that point
+    pub fn world(self, pixel_size: f32) -> f32 {
+        match self {
+            LineWidth::World(width) => width,
+            LineWidth::Pixels(width) => width * pixel_size,
+        }
+    }
+}
+
+impl LineStyle {
+    pub fn new(width: LineWidth, cap: LineCap, join: LineJoin) -> Self {
+        Self { width, cap, join }
+    }
+}
+
+impl Default for LineStyle {
+    fn default() -> Self {
+        Self::new(LineWidth::Pixels(1.0), LineCap::Butt, LineJoin::Miter)
+    }
+}
+
+impl Facing {
+    // face the camera, using the screen height to find the size of a pixel
+    pub fn camera(camera: &Camera, screen_height: u32) -> Self {
+        Facing::Camera {
+            position: camera.position(),
+            pixel_size: 2.0 * (camera.fovy / 2.0).tan() / screen_height.max(1) as f32,
+        }
+    }
+
+    // face along the average of two vertex normals, falling back to +z
+    pub fn from_normals(a: Normal, b: Normal) -> Self {
+        let normal = (a.normalize() + b.normalize()).normalize();
+
+        if normal.length() == 0.0 {
+            return Facing::Fixed(Normal::new(0.0, 0.0, 1.0));
+        }
+
+        Facing::Fixed(normal)
+    }
+
+    // facing direction and size of a pixel at a point
+    fn at(self, point: Position) -> (Normal, f32) {
+        match self {
+            Facing::Fixed(normal) => (normal.normalize(), 1.0),
+            Facing::Camera { position, pixel_size } => {
+                let distance = point.distance(position);
+
+                (offset(point, position).normalize(), distance * pixel_size)
+            }
+        }
+    }
+}
+
 impl Line {
-    // create a line mesh based on a start and end point, thickness, and subdivision rate
+    // create a line mesh based on a start and end point, pixel thickness, and subdivision rate
     pub fn new(start: Vertex, end: Vertex, thickness: f32, subdivision: u32) -> Self {
+        let style = LineStyle::new(LineWidth::Pixels(thickness), LineCap::Butt, LineJoin::Miter);
+
+        Self::styled(start, end, style, Facing::from_normals(start.normal, end.normal), subdivision)
+    }
+
+    // create a line with its own width, caps and facing
+    pub fn styled(start: Vertex, end: Vertex, style: LineStyle, facing: Facing, subdivision: u32) -> Self {
         Self {
             start_position: start.position,
             end_position: end.position,
             start_color: start.color,
             end_color: end.color,
-            thickness,
+            style,
+            facing,
             subdivision,
-            mesh: Self::line(start, end, thickness, subdivision),
+            mesh: Self::line(start, end, style, facing, subdivision),
+        }
+    }
+
+    // split the line into segments and extrude them into quads
+    pub fn line(start_vertex: Vertex, end_vertex: Vertex, style: LineStyle, facing: Facing, subdivision: u32) -> Mesh {
+        let segments = subdivision.max(1);
+
+        let points: Vec<Position> = (0..=segments)
+            .map(|i| start_vertex.position.interpolate(end_vertex.position, i as f32 / segments as f32))
+            .collect();
+
+        Self::extrude(&points, start_vertex.color, end_vertex.color, style, facing)
+    }
+
+    // rebuild the mesh so it faces the camera
+    pub fn face_camera(&mut self, camera: &Camera, screen_height: u32) {
+        self.facing = Facing::camera(camera, screen_height);
+        self.rebuild();
+    }
+
+    // rebuild the mesh with a new style
+    pub fn set_style(&mut self, style: LineStyle) {
+        self.style = style;
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self) {
+        let start = Vertex::new(self.start_position, self.start_color, Normal::new(0.0, 0.0, 0.0));
+        let end = Vertex::new(self.end_position, self.end_color, Normal::new(0.0, 0.0, 0.0));
+
+        self.mesh = Self::line(start, end, self.style, self.facing, self.subdivision);
+    }
+
+    // extrude a path of points into a triangle list, blending the color from start to end along its length
+    pub fn extrude(points: &[Position], start_color: Color, end_color: Color, style: LineStyle, facing: Facing) -> Mesh {
+        let mut mesh = Mesh::new(Vec::new(), Vec::new());
+        let mut extrusion = Extrusion::default();
+
+        for point in points {
+            extrusion.push(&mut mesh, *point, style, facing);
         }
+        extrusion.paint(&mut mesh, start_color, end_color);
+        mesh.update_bounds();
+
+        mesh
     }
 
-    // calculate length, direction, normal, and create a line mesh based on a thickness and subdivision rate
-    pub fn line(start_vertex: Vertex, end_vertex: Vertex, _thickness: f32, _subdivision: u32) -> Mesh {
-        // determine the length of the mesh
-        let _length = Mesh::vertex_length(start_vertex.position.into(), end_vertex.position.into());
+    // extrude a lone segment with butt ends straight onto the end of a vertex and index list, skipping one with no length
+    pub fn segment(a: Position, b: Position, color: Color, width: LineWidth, facing: Facing, vertices: &mut Vec<Vertex>, indices: &mut Vec<u32>) {
+        if a.distance(b) <= f32::EPSILON {
+            return;
+        }
+
+        let direction = offset(a, b).normalize();
+        let first = Mesh::index_of(vertices.len());
+        for point in [a, b] {
+            let (normal, pixel_size) = facing.at(point);
+            let side = side(direction, normal) * (width.world(pixel_size) / 2.0);
+
+            vertices.push(Vertex::new(point + side, color, Normal::new(0.0, 0.0, 0.0)));
+            vertices.push(Vertex::new(point + side * -1.0, color, Normal::new(0.0, 0.0, 0.0)));
+        }
+        // the last vertex has to stay addressable too
+        Mesh::index_of(vertices.len() - 1);
 
-        // return new mesh
-        Mesh::new(
-            // create vertices
-             vec![start_vertex, end_vertex],
-            // create indices
-         vec![0, 1]
-        )
+        let (left, right, end_left, end_right) = (first, first + 1, first + 2, first + 3);
+        indices.extend_from_slice(&[right, end_right, left, left, end_right, end_left]);
     }
 }
 
+// a path extruded point by point, remembering where each point was built so adding to the end only rebuilds the old end point
+#[derive(Debug, Clone, Default)]
+pub struct Extrusion {
+    path: Vec<Position>,
+    // distance along the path at each point, used for the gradient
+    lengths: Vec<f32>,
+    // vertex and index counts before each point was built, and the pair its outgoing segment starts from
+    built: Vec<(usize, usize, (u32, u32))>,
+}
+
+impl Extrusion {
+    // add a point to the end of the path and build it into the mesh, returning the first vertex built or none for a repeated point
+    // only the old end point and the new segment are built, colors are left for paint
+    pub fn push(&mut self, mesh: &mut Mesh, point: Position, style: LineStyle, facing: Facing) -> Option<usize> {
+        // repeated points have no direction to extrude along
+        if let Some(last) = self.path.last() {
+            if last.distance(point) <= f32::EPSILON {
+                return None;
+            }
+        }
+
+        let length = self.path.last().zip(self.lengths.last())
+            .map(|(last, length)| length + last.distance(point))
+            .unwrap_or(0.0);
+        self.path.push(Position::new(point.x, point.y, point.z, 1.0));
+        self.lengths.push(length);
+
+        let last = self.path.len() - 1;
+        if last == 0 {
+            return Some(mesh.vertices.len());
+        }
+
+        // the old end point was capped, drop it so it can be built again as a join
+        if let Some(&(vertices, indices, _)) = self.built.get(last - 1) {
+            mesh.vertices.truncate(vertices);
+            mesh.indices.truncate(indices);
+            self.built.truncate(last - 1);
+        }
+
+        let first = mesh.vertices.len();
+        for i in self.built.len()..=last {
+            self.build(mesh, i, style, facing);
+        }
+
+        Some(first)
+    }
+
+    // blend the color of every point's vertices from start to end along the length of the path
+    pub fn paint(&self, mesh: &mut Mesh, start_color: Color, end_color: Color) {
+        let total = self.lengths.last().copied().unwrap_or(0.0);
+
+        for (i, &(first, _, _)) in self.built.iter().enumerate() {
+            let end = self.built.get(i + 1).map(|built| built.0).unwrap_or(mesh.vertices.len());
+            let color = start_color.interpolate(end_color, self.lengths[i] / total);
+
+            for vertex in &mut mesh.vertices[first..end] {
+                vertex.color = color;
+            }
+        }
+    }
+
+    // build the joint or cap at one point and the segment leading into it
+    fn build(&mut self, mesh: &mut Mesh, i: usize, style: LineStyle, facing: Facing) {
+        let path = &self.path;
+        let last = path.len() - 1;
+        let (vertices, indices) = (mesh.vertices.len(), mesh.indices.len());
+        let mut builder = Builder { mesh };
+
+        let (normal, pixel_size) = facing.at(path[i]);
+        let half = style.width.world(pixel_size) / 2.0;
+        let color = Color::white();
+
+        let incoming = if i > 0 { Some(offset(path[i - 1], path[i]).normalize()) } else { None };
+        let outgoing = if i < last { Some(offset(path[i], path[i + 1]).normalize()) } else { None };
+
+        // square caps push the end points out by half the width
+        let mut point = path[i];
+        if style.cap == LineCap::Square {
+            match (incoming, outgoing) {
+                (None, Some(direction)) => point = point + direction * -half,
+                (Some(direction), None) => point = point + direction * half,
+                _ => {}
+            }
+        }
+
+        let (end_pair, start_pair) = match (incoming, outgoing) {
+            (Some(before), Some(after)) => {
+                let side_before = side(before, normal);
+                let side_after = side(after, normal);
+                let miter = (side_before + side_after).normalize();
+                let cos = miter.dot(side_before);
+
+                if style.join == LineJoin::Miter && cos > 1.0 / MITER_LIMIT {
+                    let pair = builder.pair(point, miter * (half / cos), color);
+
+                    (pair, pair)
+                } else {
+                    let end_pair = builder.pair(point, side_before * half, color);
+                    let start_pair = builder.pair(point, side_after * half, color);
+                    let center = builder.push(point, color);
+
+                    // fill the gap on the outside of the turn
+                    if before.cross(after).dot(normal) > 0.0 {
+                        builder.triangle(center, end_pair.1, start_pair.1);
+                    } else {
+                        builder.triangle(center, start_pair.0, end_pair.0);
+                    }
+
+                    (end_pair, start_pair)
+                }
+            }
+            (Some(direction), None) | (None, Some(direction)) => {
+                let pair = builder.pair(point, side(direction, normal) * half, color);
+
+                (pair, pair)
+            }
+            (None, None) => unreachable!(),
+        };
+
+        if let Some(&(_, _, (left, right))) = i.checked_sub(1).and_then(|previous| self.built.get(previous)) {
+            builder.triangle(right, end_pair.1, left);
+            builder.triangle(left, end_pair.1, end_pair.0);
+        }
+
+        if style.cap == LineCap::Round {
+            match (incoming, outgoing) {
+                (None, Some(direction)) => builder.round_cap(point, side(direction, normal) * half, direction * -half, color),
+                (Some(direction), None) => builder.round_cap(point, side(direction, normal) * -half, direction * half, color),
+                _ => {}
+            }
+        }
+
+        self.built.push((vertices, indices, start_pair));
+    }
+}
+
+// adds the vertices and triangles of an extruded line to a mesh
+struct Builder<'a> {
+    mesh: &'a mut Mesh,
+}
+
+impl Builder<'_> {
+    // lines are drawn unlit, so their normals are left at zero
+    fn push(&mut self, position: Position, color: Color) -> u32 {
+        self.mesh.vertices.push(Vertex::new(position, color, Normal::new(0.0, 0.0, 0.0)));
+
+        Mesh::index_of(self.mesh.vertices.len() - 1)
+    }
+
+    // left and right vertices either side of a point
+    fn pair(&mut self, point: Position, side: Normal, color: Color) -> (u32, u32) {
+        (self.push(point + side, color), self.push(point + side * -1.0, color))
+    }
+
+    fn triangle(&mut self, a: u32, b: u32, c: u32) {
+        self.mesh.indices.extend_from_slice(&[a, b, c]);
+    }
+
+    // half circle fanned around a point, sweeping from the first edge through the tip
+    fn round_cap(&mut self, point: Position, edge: Normal, tip: Normal, color: Color) {
+        let center = self.push(point, color);
+
+        let mut previous = self.push(point + edge, color);
+        for k in 1..=ROUND_CAP_SEGMENTS {
+            let angle = PI * k as f32 / ROUND_CAP_SEGMENTS as f32;
+            let next = self.push(point + edge * angle.cos() + tip * angle.sin(), color);
+
+            self.triangle(center, previous, next);
+            previous = next;
+        }
+    }
+}
+
+// vector from one point to another
+fn offset(from: Position, to: Position) -> Normal {
+    Normal::new(to.x - from.x, to.y - from.y, to.z - from.z)
+}
+
+// unit vector to the left of a direction when looking back along the facing normal
+fn side(direction: Normal, facing: Normal) -> Normal {
+    let side = facing.cross(direction).normalize();
+
+    if side.length() > 0.0 {
+        return side;
+    }
+
+    // the line points straight along the facing, so any perpendicular will do
+    let axis = if direction[0].abs() < 0.9 { Normal::new(1.0, 0.0, 0.0) } else { Normal::new(0.0, 1.0, 0.0) };
+
+    axis.cross(direction).normalize()
+}
+
 // implement line formatting
 impl std::fmt::Display for Line {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, 
+        write!(f,
                 "Line:
-                    start_position: {}, 
-                    end_position: {}, 
-                    start_color: {}, 
-                    end_color: {}, 
-                    thickness: {}, 
-                    subdivision: {}, 
+                    start_position: {},
+                    end_position: {},
+                    start_color: {},
+                    end_color: {},
+                    style: {:?},
+                    subdivision: {},
                     mesh: \n{}
-                ", 
-        self.start_position, self.end_position, self.start_color, self.end_color, self.thickness, self.subdivision, self.mesh)
+                ",
+        self.start_position, self.end_position, self.start_color, self.end_color, self.style, self.subdivision, self.mesh)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn style(cap: LineCap, join: LineJoin) -> LineStyle {
+        LineStyle::new(LineWidth::World(2.0), cap, join)
     }
-}
\ No newline at end of file
+
+    fn facing() -> Facing {
+        Facing::Fixed(Normal::new(0.0, 0.0, 1.0))
+    }
+
+    // every triangle should be wound counter clockwise when seen from +z
+    fn assert_faces_forward(mesh: &Mesh) {
+        assert!(!mesh.indices.is_empty());
+
+        for face in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| mesh.vertices[face[k] as usize].position);
+            let area = (b.x - a.x) * (c.y - a.y) - (b.y - a.y) * (c.x - a.x);
+            assert!(area >= -1e-5);
+        }
+    }
+
+    #[test]
+    fn test_line_extrudes_to_width_with_gradient() {
+        let start = Vertex::new(Position::new(0.0, 0.0, 0.0, 1.0), Color::new(1.0, 0.0, 0.0, 1.0), Normal::new(0.0, 0.0, 1.0));
+        let end = Vertex::new(Position::new(10.0, 0.0, 0.0, 1.0), Color::new(0.0, 0.0, 1.0, 1.0), Normal::new(0.0, 0.0, 1.0));
+        let line = Line::new(start, end, 2.0, 4);
+
+        assert_faces_forward(&line.mesh);
+        assert_eq!(line.mesh.indices.len(), 4 * 6);
+
+        for vertex in &line.mesh.vertices {
+            assert!((vertex.position.y.abs() - 1.0).abs() < 1e-5);
+            assert!((vertex.color.r - (1.0 - vertex.position.x / 10.0)).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_caps_extend_past_the_ends() {
+        let points = [Position::new(0.0, 0.0, 0.0, 1.0), Position::new(4.0, 0.0, 0.0, 1.0)];
+
+        for cap in [LineCap::Square, LineCap::Round] {
+            let mesh = Line::extrude(&points, Color::white(), Color::white(), style(cap, LineJoin::Miter), facing());
+            assert_faces_forward(&mesh);
+
+            let min = mesh.vertices.iter().map(|v| v.position.x).fold(f32::MAX, f32::min);
+            let max = mesh.vertices.iter().map(|v| v.position.x).fold(f32::MIN, f32::max);
+            assert!((min + 1.0).abs() < 1e-5 && (max - 5.0).abs() < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_joins_keep_the_width_around_corners() {
+        let points = [
+            Position::new(0.0, 0.0, 0.0, 1.0),
+            Position::new(4.0, 0.0, 0.0, 1.0),
+            Position::new(4.0, 4.0, 0.0, 1.0),
+            Position::new(8.0, 4.0, 0.0, 1.0),
+        ];
+
+        // a right angle miter reaches out to the corner of the square around the joint
+        let mesh = Line::extrude(&points, Color::white(), Color::white(), style(LineCap::Butt, LineJoin::Miter), facing());
+        assert_faces_forward(&mesh);
+        assert_eq!(mesh.vertices.len(), 8);
+        assert!(mesh.vertices.iter().any(|v| v.position.distance(Position::new(5.0, -1.0, 0.0, 1.0)) < 1e-5));
+
+        // a bevel cuts the corner off with an extra triangle per joint
+        let mesh = Line::extrude(&points, Color::white(), Color::white(), style(LineCap::Butt, LineJoin::Bevel), facing());
+        assert_faces_forward(&mesh);
+        assert_eq!(mesh.indices.len(), 3 * 6 + 2 * 3);
+        assert!(mesh.vertices.iter().all(|v| v.position.x <= 8.0 + 1e-5 && v.position.y >= -1.0 - 1e-5));
+    }
+}
@@ -1,4 +1,6 @@
 mod line;
+mod polyline;
+mod curve;
 mod triangle;
 mod quad;
 mod cube;
@@ -8,6 +10,8 @@ mod sphere;
 mod geometry;
 
 pub use self::line::*;
+pub use self::polyline::*;
+pub use self::curve::*;
 pub use self::triangle::*;
 pub use self::quad::*;
 pub use self::cube::*;
@@ -0,0 +1,145 @@
+use crate::graphics::position::Position;
+use crate::graphics::color::Color;
+use crate::graphics::mesh::Mesh;
+use crate::graphics::camera::Camera;
+use crate::graphics::bounds::{Aabb, BoundingSphere};
+use crate::graphics::{LineStyle, Facing, Extrusion};
+
+// thick line through any number of points, joined at every corner
+pub struct Polyline {
+    pub points: Vec<Position>,
+    pub start_color: Color,
+    pub end_color: Color,
+    pub style: LineStyle,
+    pub facing: Facing,
+    pub mesh: Mesh,
+    extrusion: Extrusion,
+}
+
+impl Polyline {
+    pub fn new(points: Vec<Position>, start_color: Color, end_color: Color, style: LineStyle, facing: Facing) -> Self {
+        let mut polyline = Self {
+            points,
+            start_color,
+            end_color,
+            style,
+            facing,
+            mesh: Mesh::new(Vec::new(), Vec::new()),
+            extrusion: Extrusion::default(),
+        };
+        polyline.rebuild();
+
+        polyline
+    }
+
+    // add a point to the end of the line, building only the last segment
+    // the gradient runs along the whole length, so the colors of the earlier points are refreshed
+    pub fn push(&mut self, point: Position) {
+        self.points.push(point);
+
+        let Some(first) = self.extrusion.push(&mut self.mesh, point, self.style, self.facing) else {
+            return;
+        };
+        self.extrusion.paint(&mut self.mesh, self.start_color, self.end_color);
+
+        // the old end point was only rebuilt in place, so the bounds just grow around the new vertices
+        if first == 0 {
+            self.mesh.update_bounds();
+        } else {
+            let tail: Vec<Position> = self.mesh.vertices[first..].iter().map(|vertex| vertex.position).collect();
+
+            self.mesh.aabb = self.mesh.aabb.union(&Aabb::from_points(tail.iter().copied()));
+            self.mesh.bounding_sphere = self.mesh.bounding_sphere.union(&BoundingSphere::from_points(&tail));
+        }
+    }
+
+    // rebuild the mesh so it faces the camera
+    pub fn face_camera(&mut self, camera: &Camera, screen_height: u32) {
+        self.facing = Facing::camera(camera, screen_height);
+        self.rebuild();
+    }
+
+    // rebuild the mesh with a new style
+    pub fn set_style(&mut self, style: LineStyle) {
+        self.style = style;
+        self.rebuild();
+    }
+
+    fn rebuild(&mut self) {
+        self.mesh = Mesh::new(Vec::new(), Vec::new());
+        self.extrusion = Extrusion::default();
+
+        for point in &self.points {
+            self.extrusion.push(&mut self.mesh, *point, self.style, self.facing);
+        }
+        self.extrusion.paint(&mut self.mesh, self.start_color, self.end_color);
+        self.mesh.update_bounds();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::{Normal, LineWidth, LineCap, LineJoin};
+
+    fn polyline(points: Vec<Position>, join: LineJoin) -> Polyline {
+        let style = LineStyle::new(LineWidth::World(2.0), LineCap::Round, join);
+        let facing = Facing::Fixed(Normal::new(0.0, 0.0, 1.0));
+
+        Polyline::new(points, Color::new(1.0, 0.0, 0.0, 1.0), Color::new(0.0, 0.0, 1.0, 1.0), style, facing)
+    }
+
+    fn zigzag() -> Vec<Position> {
+        vec![
+            Position::new(0.0, 0.0, 0.0, 1.0),
+            Position::new(4.0, 0.0, 0.0, 1.0),
+            Position::new(4.0, 4.0, 0.0, 1.0),
+            Position::new(8.0, 4.0, 0.0, 1.0),
+            Position::new(8.0, 0.0, 0.0, 1.0),
+        ]
+    }
+
+    #[test]
+    fn test_joins_at_every_corner() {
+        let mitered = polyline(zigzag(), LineJoin::Miter);
+        let bevelled = polyline(zigzag(), LineJoin::Bevel);
+
+        // a bevel adds a centre vertex, a second pair and a triangle at each of the three corners
+        assert_eq!(bevelled.mesh.vertices.len(), mitered.mesh.vertices.len() + 3 * 3);
+        assert_eq!(bevelled.mesh.indices.len(), mitered.mesh.indices.len() + 3 * 3);
+
+        // the right angle miters reach out to the corner of the square around each joint
+        for corner in [Position::new(5.0, -1.0, 0.0, 1.0), Position::new(3.0, 5.0, 0.0, 1.0), Position::new(9.0, 5.0, 0.0, 1.0)] {
+            assert!(mitered.mesh.vertices.iter().any(|v| v.position.distance(corner) < 1e-5), "{}", corner);
+        }
+    }
+
+    #[test]
+    fn test_push_matches_building_all_at_once() {
+        for join in [LineJoin::Miter, LineJoin::Bevel] {
+            let points = zigzag();
+            let expected = polyline(points.clone(), join);
+
+            let mut pushed = polyline(Vec::new(), join);
+            for point in points {
+                pushed.push(point);
+                // a repeated point is kept but adds nothing to extrude
+                pushed.push(point);
+            }
+
+            assert_eq!(pushed.points.len(), 10);
+            assert_eq!(pushed.mesh.indices, expected.mesh.indices);
+            assert_eq!(pushed.mesh.vertices.len(), expected.mesh.vertices.len());
+            for (vertex, expected) in pushed.mesh.vertices.iter().zip(&expected.mesh.vertices) {
+                assert!(vertex.position.distance(expected.position) < 1e-5);
+                assert!((vertex.color.r - expected.color.r).abs() < 1e-5 && (vertex.color.b - expected.color.b).abs() < 1e-5);
+            }
+
+            // the bounds only grow, so they still hold every vertex
+            for vertex in &pushed.mesh.vertices {
+                assert!(pushed.mesh.aabb.contains(vertex.position));
+                assert!(pushed.mesh.bounding_sphere.radius + 1e-4 >= pushed.mesh.bounding_sphere.center.distance(vertex.position));
+            }
+        }
+    }
+}
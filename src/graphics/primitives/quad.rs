@@ -4,6 +4,7 @@ use crate::graphics::Position;
 use crate::graphics::Color;
 use crate::graphics::Normal;
 use crate::graphics::Geometry;
+use crate::graphics::SubdivisionError;
 
 #[derive(Debug, Clone)]
 pub struct Square {
@@ -43,19 +44,19 @@ impl Square {
         );
 
         let v2 = Vertex::new(
-            Position::new(x - offset, y - offset, 0.0, 1.0), 
+            Position::new(x - offset, y - offset, z, 1.0), 
             Color::black(), 
             Normal::new(0.0, 0.0, 1.0),
         );
 
         let v3 = Vertex::new(
-            Position::new(x + offset, y + offset, 0.0, 1.0), 
+            Position::new(x + offset, y + offset, z, 1.0), 
             Color::magenta(), 
             Normal::new(0.0, 0.0, 1.0)
         );
 
         let v4 = Vertex::new(
-            Position::new(x + offset, y - offset, 0.0, 1.0), 
+            Position::new(x + offset, y - offset, z, 1.0), 
             Color::yellow(), 
             Normal::new(0.0, 0.0, 1.0)
         );
@@ -87,70 +88,54 @@ impl Square {
         self.mesh.dedup();
     }
 
-    // subdivide a square based on a subdivision rate
-    pub fn subdivide(&mut self, n_subdivisions: u32) {
-        let mut vertices = vec![];
-        let mut indices = vec![];
-
-        // get the four vertices of the square
-        let v1 = self.mesh.vertices[0];
-        let v2 = self.mesh.vertices[1];
-        let v3 = self.mesh.vertices[2];
-        let v4 = self.mesh.vertices[3];
-
-        // calculate the number of subdivisions
-        let n = 1 << n_subdivisions;
-        // calculate the step size
-        let step = 1.0 / n as f32;
-    
-        // create the vertices
-        for i in 0..=n {
-            let t1 = i as f32 * step;
-            for j in 0..=n {
-                let t2 = j as f32 * step;
-    
-                // calculate the position of the vertex
-                let position = v1.position * (1.0 - t1) * (1.0 - t2)
-                    + v2.position * (1.0 - t1) * t2
-                    + v3.position * t1 * (1.0 - t2)
-                    + v4.position * t1 * t2;
-    
-                // calculate the color of the vertex
-                let color = v1.color * (1.0 - t1) * (1.0 - t2)
-                    + v2.color * (1.0 - t1) * t2
-                    + v3.color * t1 * (1.0 - t2)
-                    + v4.color * t1 * t2;
-    
-                // calculate the normal of the vertex
-                let normal = v1.normal * (1.0 - t1) * (1.0 - t2)
-                    + v2.normal * (1.0 - t1) * t2
-                    + v3.normal * t1 * (1.0 - t2)
-                    + v4.normal * t1 * t2;
-    
-                // add the vertex to the list
-                vertices.push(Vertex::new(position, color, normal));
-            }
+    // subdivide the square, splitting every triangle of the current mesh so repeated calls and earlier edits carry over
+    // midpoints are shared between neighbouring triangles, so the grid needs no deduplication
+    pub fn subdivide(&mut self, n_subdivisions: u32) -> Result<(), SubdivisionError> {
+        self.mesh.subdivide(n_subdivisions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subdivide_twice_keeps_the_square() {
+        let origin = Position::new(1.0, -1.0, 0.5, 1.0);
+        let mut square = Square::quad(origin, 2.0);
+        square.subdivide(1).unwrap();
+        square.subdivide(1).unwrap();
+
+        // a 5 by 5 grid split into 32 triangles, the same as subdividing twice at once
+        let mut once = Square::quad(origin, 2.0);
+        once.subdivide(2).unwrap();
+        assert_eq!(square.mesh.vertices.len(), 25);
+        assert_eq!(square.mesh.indices.len(), 32 * 3);
+        assert_eq!(square.mesh.indices, once.mesh.indices);
+
+        for triangle in square.mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| square.mesh.vertices[triangle[k] as usize].position);
+
+            // every triangle still faces +z with a quarter of a quarter of the area
+            let normal = Mesh::normalize(a, b, c);
+            assert!(normal[2] > 0.99);
+            assert!(((b - a).cross(c - a).z / 2.0 - 4.0 / 32.0).abs() < 1e-5);
         }
-    
-        // create the indices
-        for i in 0..n {
-            for j in 0..n {
-                let index = i * (n + 1) + j;
-
-                // triangle 1
-                indices.push((index + n + 1) as u16);
-                indices.push(index as u16);
-                indices.push((index + (n + 1) + 1) as u16);
-
-                // triangle 2
-                indices.push(index as u16);
-                indices.push((index + 1) as u16);
-                indices.push((index + (n + 1) + 1) as u16);
-            }
+
+        for vertex in &square.mesh.vertices {
+            let p = vertex.position;
+            assert!((p.x - origin.x).abs() <= 1.0 + 1e-6 && (p.y - origin.y).abs() <= 1.0 + 1e-6 && p.z == origin.z);
         }
+    }
+
+    #[test]
+    fn test_subdivide_overflow_leaves_the_square() {
+        let mut square = Square::quad(Position::new(0.0, 0.0, 0.0, 1.0), 1.0);
 
-        // create the mesh
-        self.mesh = Mesh::new(vertices, indices);
-        self.dedup();
+        // a 65537 by 65537 grid no longer fits a u32 index, a 32769 by 32769 one still does
+        assert_eq!(square.subdivide(16), Err(SubdivisionError::TooManyVertices { level: 16 }));
+        assert!(square.mesh.subdivision_fits(15).is_ok());
+        assert_eq!(square.mesh.vertices.len(), 4);
+        assert_eq!(square.mesh.indices.len(), 6);
     }
 }
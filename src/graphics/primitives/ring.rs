@@ -1,86 +1,225 @@
-    use crate::graphics::Vertex;
-    use crate::graphics::Position;
-    use crate::graphics::Color;
-    use crate::graphics::Mesh;
-
-    pub struct Ring {
-        pub center: Position,
-        pub radius: f32,
-        pub thickness: f32,
-        pub subdivision: u32,
-        pub color: Color,
-        pub mesh: Mesh,
+use std::f32::consts::PI;
+use crate::graphics::{Geometry, Vertex, Position, Normal, Vec3, Quat, Color, Mesh, SubdivisionError};
+
+// default number of segments around a full ring
+pub const RING_SUBDIVISION: u32 = 64;
+
+// how close to a full turn, in radians, an arc has to be to close into a ring
+const CLOSED_TOLERANCE: f32 = 1e-4;
+
+// flat annulus, or an arc of one, lying in the plane facing its axis
+#[derive(Debug, Clone)]
+pub struct Ring {
+    pub center: Position,
+    pub radius: f32,
+    pub thickness: f32,
+    pub subdivision: u32,
+    pub color: Color,
+    // angles in radians, measured counter-clockwise around the axis
+    pub start_angle: f32,
+    pub end_angle: f32,
+    // the axis the ring was built facing, before any rotation
+    pub axis: Normal,
+    // every rotation applied so far, kept so a rebuilt mesh faces and starts the same way
+    pub rotation: Quat,
+    pub mesh: Mesh,
+}
+
+impl Ring {
+    pub fn new(center: Position, radius: f32, thickness: f32, subdivision: u32, color: Color) -> Geometry {
+        Geometry::Ring(Self::ring(center, radius, thickness, subdivision, color))
+    }
+
+    // instantiate a full ring facing +z
+    pub fn ring(center: Position, radius: f32, thickness: f32, subdivision: u32, color: Color) -> Self {
+        Self::arc(center, radius, thickness, subdivision, color, 0.0, 2.0 * PI, Normal::new(0.0, 0.0, 1.0))
+    }
+
+    // instantiate part of a ring between two angles, facing along an axis
+    #[allow(clippy::too_many_arguments)]
+    pub fn arc(center: Position, radius: f32, thickness: f32, subdivision: u32, color: Color, start_angle: f32, end_angle: f32, axis: Normal) -> Self {
+        Self {
+            center,
+            radius,
+            thickness,
+            subdivision,
+            color,
+            start_angle,
+            end_angle,
+            axis,
+            rotation: Quat::IDENTITY,
+            mesh: Self::annulus(center, radius, thickness, subdivision, color, start_angle, end_angle, axis),
+        }
+    }
+
+    // create a ring mesh as a triangle list, closing the loop when the arc covers the whole circle
+    #[allow(clippy::too_many_arguments)]
+    pub fn annulus(center: Position, radius: f32, thickness: f32, subdivision: u32, color: Color, start_angle: f32, end_angle: f32, axis: Normal) -> Mesh {
+        let segments = subdivision.max(1);
+        let half_thickness = thickness / 2.0;
+        let start = start_angle.min(end_angle);
+        let sweep = (end_angle - start_angle).abs();
+        let closed = sweep >= 2.0 * PI - CLOSED_TOLERANCE;
+
+        // in-plane axes with u x v along the axis, so increasing angles turn counter-clockwise
+        let axis = axis.normalize();
+        let reference = if axis[0].abs() < 0.9 { Normal::new(1.0, 0.0, 0.0) } else { Normal::new(0.0, 1.0, 0.0) };
+        let u = (reference - axis * reference.dot(axis)).normalize();
+        let v = axis.cross(u);
+
+        // a closed ring reuses the first pair of vertices instead of repeating it
+        let steps = if closed { segments } else { segments + 1 };
+        let sweep = if closed { 2.0 * PI } else { sweep };
+
+        let mut vertices: Vec<Vertex> = Vec::with_capacity(steps as usize * 2);
+        for i in 0..steps {
+            let angle = start + sweep * i as f32 / segments as f32;
+            let direction = u * angle.cos() + v * angle.sin();
+
+            vertices.push(Vertex::new(center + direction * (radius + half_thickness), color, axis));
+            vertices.push(Vertex::new(center + direction * (radius - half_thickness), color, axis));
+        }
+
+        let mut indices: Vec<u32> = Vec::with_capacity(segments as usize * 6);
+        for i in 0..segments {
+            let outer = i * 2;
+            let inner = outer + 1;
+            let next_outer = (i + 1) % steps * 2;
+            let next_inner = next_outer + 1;
+
+            indices.extend_from_slice(&[
+                inner, outer, next_outer,
+                inner, next_outer, next_inner,
+            ]);
+        }
+
+        Mesh::new(vertices, indices)
+    }
+
+    // regenerate the ring with twice the segments per level, leaving it untouched when the result would not fit
+    pub fn subdivide(&mut self, level: u32) -> Result<(), SubdivisionError> {
+        let subdivision = SubdivisionError::doubled(self.subdivision, level)?;
+
+        // an inner and outer vertex at every step, with one more step when the arc is open
+        if (subdivision as u64 + 1) * 2 >= u32::MAX as u64 {
+            return Err(SubdivisionError::TooManyVertices { level });
+        }
+
+        self.subdivision = subdivision;
+        self.mesh = Self::annulus(self.center, self.radius, self.thickness, self.subdivision, self.color, self.start_angle, self.end_angle, self.axis);
+        if self.rotation != Quat::IDENTITY {
+            self.mesh.rotate_by(self.rotation, self.center);
+        }
+
+        Ok(())
+    }
+
+    // rotate the ring around its center, remembered so subdividing keeps it
+    pub fn rotate(&mut self, angle: f32, axis: Vec3) {
+        let rotation = Quat::from_axis_angle(axis.into(), angle);
+
+        self.mesh.rotate_by(rotation, self.center);
+        self.rotation = (self.rotation * rotation).normalize();
     }
-        
-    impl Ring {
-        // instantiate a new ring
-        pub fn new(center: Position, radius: f32, thickness: f32, subdivision: u32, color: Color) -> Self {
-            Self {
-                center,
-                radius,
-                thickness,
-                subdivision,
-                color,
-                mesh: Ring::ring(center, radius, thickness, subdivision, color)
-            }
+
+    // the axis the ring faces once its rotation is applied
+    pub fn facing(&self) -> Normal {
+        self.rotation.rotate(self.axis.normalize())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::Shape;
+
+    #[test]
+    fn test_ring_is_a_closed_annulus() {
+        let origin = Position::new(1.0, 0.0, 0.0, 1.0);
+        let mut ring = Geometry::new(origin, 1.0, Shape::Ring);
+        ring.subdivide(1).unwrap();
+
+        // the last segment wraps around to the first pair of vertices
+        let segments = RING_SUBDIVISION as usize * 2;
+        assert_eq!(ring.vertex_len(), segments * 2);
+        assert_eq!(ring.index_len(), segments * 6);
+        assert!(ring.indices().iter().all(|index| (*index as usize) < ring.vertex_len()));
+
+        // the triangles cover the area between the inner and outer radius
+        let mut area = 0.0;
+        for face in ring.indices().chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| ring.vertices()[face[k] as usize].position);
+            let normal = Mesh::normalize(a, b, c);
+            assert!(normal[2] > 0.99);
+            area += ((b - a).cross(c - a)).z / 2.0;
+        }
+        let expected = std::f32::consts::PI * (1.05_f32.powi(2) - 0.95_f32.powi(2));
+        assert!((area - expected).abs() < expected * 0.01);
+    }
+
+    #[test]
+    fn test_ring_arc_faces_its_axis() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let axis = Normal::new(1.0, 1.0, 0.0).normalize();
+        let arc = Ring::arc(origin, 2.0, 0.5, 8, Color::white(), 0.0, std::f32::consts::PI / 2.0, axis);
+
+        // an open arc has a pair of vertices at both ends
+        assert_eq!(arc.mesh.vertices.len(), 9 * 2);
+        assert_eq!(arc.mesh.indices.len(), 8 * 6);
+
+        for face in arc.mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| arc.mesh.vertices[face[k] as usize].position);
+            assert!(Mesh::normalize(a, b, c).dot(axis) > 0.99);
         }
 
+        for vertex in &arc.mesh.vertices {
+            let distance = vertex.position.distance(origin);
+            assert!(distance > 1.75 - 1e-4 && distance < 2.25 + 1e-4);
+            assert!(Normal::from(vertex.position).dot(axis).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_subdivide_overflow_leaves_the_ring() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let mut ring = Ring::ring(origin, 1.0, 0.1, RING_SUBDIVISION, Color::white());
+        let before = ring.mesh.vertices.len();
+
+        assert_eq!(ring.subdivide(32), Err(SubdivisionError::ResolutionOverflow { resolution: RING_SUBDIVISION, level: 32 }));
+        assert_eq!(ring.subdivide(26), Err(SubdivisionError::ResolutionOverflow { resolution: RING_SUBDIVISION, level: 26 }));
+        assert_eq!(ring.subdivide(25), Err(SubdivisionError::TooManyVertices { level: 25 }));
+        assert_eq!(ring.subdivision, RING_SUBDIVISION);
+        assert_eq!(ring.mesh.vertices.len(), before);
+    }
 
-        // create a circular ring mesh based on a center point, radius, color, line thickness, and subdivision rate
-        pub fn ring(center: Position, radius: f32, thickness: f32, subdivision: u32, color: Color) -> Mesh {
-            // create vertices, angles, color, and normal
-            let mut vertices: Vec<Vertex> = Vec::new();
-            let mut indices: Vec<u16> = Vec::new();
-            let angle: f32 = 2.0 * std::f32::consts::PI / subdivision as f32;
-            let axis: Position = [0.0, 0.0, 1.0, 0.0].into();
-            let half_thickness: f32 = thickness / 2.0;
-
-            // create the first two corners
-            let mut p1 = Position::new(center[0] + radius + half_thickness, center[1], center[2], 1.0);
-            let mut p2 = Position::new(center[0] + radius - half_thickness, center[1], center[2], 1.0);
-
-            // create the first two normals
-            let mut normal1 = Mesh::normalize(center, p1, p2);
-            let mut normal2 = Mesh::normalize(center, p2, p1);
-
-            
-            // create the first two vertices
-            vertices.push(Vertex::new(p1, color, normal1));
-            vertices.push(Vertex::new(p2, color, normal2));
-
-            // create the first two indices
-            indices.push(0);
-            indices.push(1);
-
-            // create the rest of the vertices and indices
-            for i in 1..subdivision {
-                // calculate the next two corners
-                let next_p1 = p1.rotate(angle, center, axis);
-                let next_p2 = p2.rotate(angle, center, axis);
-
-                // calculate the next two normals
-                normal1 =  Mesh::normalize(p1, next_p1, next_p2);
-                normal2 =  Mesh::normalize(p2, next_p2, next_p1);
-
-                // add the vertices
-                vertices.push(Vertex::new(next_p1, color, normal1));
-                vertices.push(Vertex::new(next_p2, color, normal2));
-
-                // add indices in triangle strip order
-                indices.push((i * 2) as u16);
-                indices.push((i * 2 + 1) as u16);
-                
-
-                // update p1 and p2
-                p1 = next_p1;
-                p2 = next_p2;
-            }
-
-            // update original vertices normals 
-            vertices[0].normal = Mesh::normalize(vertices[vertices.len() - 2].position, vertices[0].position, vertices[1].position);
-            vertices[1].normal = Mesh::normalize(vertices[vertices.len() - 1].position, vertices[1].position, vertices[0].position);
-
-            // create mesh
-            Mesh{vertices, indices}
+    #[test]
+    fn test_subdivide_keeps_a_spun_arc_in_place() {
+        let origin = Position::new(1.0, 2.0, 0.0, 1.0);
+        let mut arc = Ring::arc(origin, 2.0, 0.5, 8, Color::white(), 0.0, PI / 2.0, Normal::new(0.0, 0.0, 1.0));
+
+        // spin about its own axis, then tilt it over
+        arc.rotate(0.7, Vec3::Z);
+        arc.rotate(0.3, Vec3::X);
+        let ends = |ring: &Ring| {
+            let vertices = &ring.mesh.vertices;
+            [vertices[0], vertices[1], vertices[vertices.len() - 2], vertices[vertices.len() - 1]].map(|vertex| vertex.position)
+        };
+        let before = ends(&arc);
+        let facing = arc.facing();
+
+        arc.subdivide(1).unwrap();
+        assert_eq!(arc.mesh.vertices.len(), 17 * 2);
+        for (after, before) in ends(&arc).iter().zip(before) {
+            assert!(after.distance(before) < 1e-5, "{} moved from {}", after, before);
         }
-    }
\ No newline at end of file
+        assert!(arc.mesh.vertices.iter().all(|vertex| (vertex.normal - facing).length() < 1e-5));
+    }
+
+    #[test]
+    fn test_nearly_full_sweep_closes() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let ring = Ring::arc(origin, 1.0, 0.1, 16, Color::white(), 0.0, 2.0 * PI - 1e-5, Normal::new(0.0, 0.0, 1.0));
+
+        assert_eq!(ring.mesh.vertices.len(), 16 * 2);
+    }
+}
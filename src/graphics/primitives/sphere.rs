@@ -1,5 +1,6 @@
 
-use crate::graphics::{Geometry, Mesh, Position, Vertex, Color};
+use std::collections::HashMap;
+use crate::graphics::{Geometry, Mesh, Position, Vertex, Color, Normal, Vec3, Quat, SubdivisionError};
 
 // TODO: Extrapolate this out into a generic / trait
 #[derive(Debug, Copy, Clone)]
@@ -19,33 +20,111 @@ impl Sphere {
     }
 }
 
+// default resolution of a uv sphere
+pub const UV_SECTORS: u32 = 72;
+pub const UV_STACKS: u32 = 36;
+
+// default number of quads along each edge of a spherified cube face
+pub const CUBE_SPHERE_RESOLUTION: u32 = 12;
+
+// one side of a spherified cube, laid out as a grid for terrain tiles
+#[derive(Debug, Clone)]
+pub struct CubeFace {
+    pub normal: [i32; 3],
+    pub resolution: u32,
+    // mesh index of every grid vertex, row by row
+    pub vertices: Vec<u32>,
+    // uv coordinate of every grid vertex, local to this face
+    pub uvs: Vec<[f32; 2]>,
+}
+
+impl CubeFace {
+    // mesh index of the vertex at column i and row j of the face
+    pub fn index(&self, i: u32, j: u32) -> u32 {
+        self.vertices[(j * (self.resolution + 1) + i) as usize]
+    }
+
+    // uv coordinate of the vertex at column i and row j of the face
+    pub fn uv(&self, i: u32, j: u32) -> [f32; 2] {
+        self.uvs[(j * (self.resolution + 1) + i) as usize]
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Spherical {
     pub sphere_type: Sphere,
     pub radius: f32,
     pub origin: Position,
+    // resolution of the uv sphere, unused by the other sphere types
+    pub sectors: u32,
+    pub stacks: u32,
+    // resolution and faces of the spherified cube, unused by the other sphere types
+    pub resolution: u32,
+    pub faces: Vec<CubeFace>,
+    // every rotation applied so far, kept so a rebuilt mesh faces the same way
+    pub rotation: Quat,
     pub mesh: Mesh,
 }
 
 impl Spherical {
     pub fn sphere(radius: f32, origin: Position, sphere_type: Sphere) -> Spherical {
-        Spherical {
+        Self::with_resolution(radius, origin, sphere_type, UV_SECTORS, UV_STACKS, CUBE_SPHERE_RESOLUTION)
+    }
+
+    // build the mesh once at the given resolution
+    fn with_resolution(radius: f32, origin: Position, sphere_type: Sphere, sectors: u32, stacks: u32, resolution: u32) -> Spherical {
+        let mut sphere = Spherical {
             sphere_type,
             radius,
             origin,
-            mesh: Spherical::mesh(radius, origin, sphere_type),
-        }
+            sectors,
+            stacks,
+            resolution,
+            faces: Vec::new(),
+            rotation: Quat::IDENTITY,
+            mesh: Mesh::new(Vec::new(), Vec::new()),
+        };
+        sphere.generate();
+
+        sphere
+    }
+
+    // create a uv sphere with a given number of sectors (longitude) and stacks (latitude)
+    pub fn uv(radius: f32, origin: Position, sectors: u32, stacks: u32) -> Spherical {
+        Self::with_resolution(radius, origin, Sphere::UVSphere, sectors, stacks, CUBE_SPHERE_RESOLUTION)
     }
 
-    fn mesh(radius: f32, origin: Position, sphere_type: Sphere) -> Mesh {
-        match sphere_type {
-            Sphere::UVSphere => Self::uv_sphere(radius, origin),
-            Sphere::Icosahedron => Self::icosahedron(radius, origin),
-            Sphere::SpherifiedCube => Self::spherified_cube(radius, origin),
+    // create a spherified cube with a given number of quads along each face edge
+    pub fn cube_sphere(radius: f32, origin: Position, resolution: u32) -> Spherical {
+        Self::with_resolution(radius, origin, Sphere::SpherifiedCube, UV_SECTORS, UV_STACKS, resolution)
+    }
+
+    // rebuild the mesh from the current radius, origin, resolution and rotation
+    fn generate(&mut self) {
+        match self.sphere_type {
+            Sphere::UVSphere => self.mesh = Self::uv_sphere(self.radius, self.origin, self.sectors, self.stacks),
+            Sphere::Icosahedron => self.mesh = Self::icosahedron(self.radius, self.origin),
+            Sphere::SpherifiedCube => {
+                let (mesh, faces) = Self::spherified_cube(self.radius, self.origin, self.resolution);
+                self.mesh = mesh;
+                self.faces = faces;
+            },
         }
+
+        if self.rotation != Quat::IDENTITY {
+            self.mesh.rotate_by(self.rotation, self.origin);
+        }
+    }
+
+    // rotate the sphere around its origin, remembered so subdividing keeps it
+    pub fn rotate(&mut self, angle: f32, axis: Vec3) {
+        let rotation = Quat::from_axis_angle(axis.into(), angle);
+
+        self.mesh.rotate_by(rotation, self.origin);
+        self.rotation = (self.rotation * rotation).normalize();
     }
 
-    fn uv_spiral_color(i: u16, j: u16, stacks: u16, sectors: u16) -> Color{
+    fn uv_spiral_color(i: u32, j: u32, stacks: u32, sectors: u32) -> Color{
         let latitude = (std::f32::consts::PI * i as f32) / stacks as f32;
         let longitude = (2.0 * std::f32::consts::PI * j as f32) / sectors as f32;
     
@@ -61,50 +140,64 @@ impl Spherical {
         Color::new(r, g, b, 1.0)
     }
 
-    fn uv_sphere(radius: f32, origin: Position) -> Mesh {
-        // generate vertices
+    // build a uv sphere with one shared vertex at each pole
+    pub fn uv_sphere(radius: f32, origin: Position, sectors: u32, stacks: u32) -> Mesh {
         let mut vertices: Vec<Vertex> = Vec::new();
-        let mut indices: Vec<u16> = Vec::new();
-        let sectors = 144;
-        let stacks = 72;
+        let mut indices: Vec<u32> = Vec::new();
+        let sectors = sectors.max(3);
+        let stacks = stacks.max(2);
 
         let sector_step = 2.0 * std::f32::consts::PI / sectors as f32;
         let stack_step = std::f32::consts::PI / stacks as f32;
 
-        // generate vertices
-        for i in 0..=stacks {
+        // create a vertex on the sphere at a stack and sector
+        let vertex = |i: u32, j: u32| {
             let stack_angle = std::f32::consts::PI / 2.0 - i as f32 * stack_step;
+            let sector_angle = j as f32 * sector_step;
+
             let xy = radius * stack_angle.cos();
+            let x = xy * sector_angle.cos();
+            let y = xy * sector_angle.sin();
             let z = radius * stack_angle.sin();
 
-            for j in 0..=sectors {
-                let sector_angle = j as f32 * sector_step;
-
-                let x = xy * sector_angle.cos();
-                let y = xy * sector_angle.sin();
+            Vertex {
+                position: Position::new(origin.x + x, origin.y + y, origin.z + z, 1.0),
+                color: Self::uv_spiral_color(i, j, stacks, sectors),
+                normal: Normal::new(x, y, z).normalize(),
+            }
+        };
 
-                vertices.push(Vertex {
-                    position: Position::new(origin.x + x, origin.y + y, origin.z + z, 1.0),
-                    color: Self::uv_spiral_color(i, j, stacks, sectors),
-                    normal: (0.0, 0.0, 1.0).into(),
-                });
+        // north pole, every ring between the poles, then the south pole
+        vertices.push(vertex(0, 0));
+        for i in 1..stacks {
+            for j in 0..sectors {
+                vertices.push(vertex(i, j));
             }
         }
+        vertices.push(vertex(stacks, 0));
 
-        // generate indices
-        for i in 0..stacks {
-            let k1 = i * (sectors + 1);
-            let k2 = k1 + sectors + 1;
-
-            for j in 0..sectors {
-                indices.push(k1 + j);
-                indices.push(k2 + j);
-                indices.push(k2 + j + 1);
+        let north = 0;
+        let south = Mesh::index_of(vertices.len()) - 1;
+        let ring = |i: u32, j: u32| 1 + (i - 1) * sectors + j % sectors;
 
-                indices.push(k1 + j);
-                indices.push(k2 + j + 1);
-                indices.push(k1 + j + 1);
+        // generate indices
+        for j in 0..sectors {
+            // triangle fan around the north pole
+            indices.extend_from_slice(&[north, ring(1, j), ring(1, j + 1)]);
+
+            // two triangles for every quad between neighbouring rings
+            for i in 1..stacks - 1 {
+                let k1 = ring(i, j);
+                let k2 = ring(i + 1, j);
+                let k1_next = ring(i, j + 1);
+                let k2_next = ring(i + 1, j + 1);
+
+                indices.extend_from_slice(&[k1, k2, k2_next]);
+                indices.extend_from_slice(&[k1, k2_next, k1_next]);
             }
+
+            // triangle fan around the south pole
+            indices.extend_from_slice(&[ring(stacks - 1, j), south, ring(stacks - 1, j + 1)]);
         }
 
         Mesh::new(vertices, indices)
@@ -156,28 +249,29 @@ impl Spherical {
         let mut vertices: Vec<Vertex> = Vec::new();
 
         for i in 0..verts.len() / 3 {
+            // project the corners of the icosahedron onto the sphere
+            let normal = Normal::new(verts[i * 3], verts[i * 3 + 1], verts[i * 3 + 2]).normalize();
+
             vertices.push(Vertex {
-                position: Position {
-                    x: verts[i * 3] * radius + origin.x,
-                    y: verts[i * 3 + 1] * radius + origin.y,
-                    z: verts[i * 3 + 2] * radius + origin.z,
-                    w: 1.0,
-                },
+                position: origin + normal * radius,
                 color: Color::new(
                     i as f32 / 12.0,
                     1.0 - (i as f32 / 12.0), 
                     2.0 - (i as f32 / 6.0) / 2.0, 
                     1.0),
-                normal: (0.0, 0.0, 1.0).into(),
+                normal,
             });
         }
 
         Mesh::new(vertices, indices)
     }
 
-    fn spherified_cube(radius: f32, origin: Position) -> Mesh {
+    // build a watertight spherified cube, sharing the vertices along face edges
+    pub fn spherified_cube(radius: f32, origin: Position, resolution: u32) -> (Mesh, Vec<CubeFace>) {
         let mut vertices: Vec<Vertex> = Vec::new();
-        let mut indices: Vec<u16> = Vec::new();
+        let mut indices: Vec<u32> = Vec::new();
+        let mut faces: Vec<CubeFace> = Vec::new();
+        let n = resolution.max(1) as i32;
 
         fn spherify(pos: Position, radius: f32) -> Position {
             let x2 = pos.x * pos.x;
@@ -197,55 +291,75 @@ impl Spherical {
                 1.0)
         }
 
-        let subdivisions = 12;
-
-        for s in 0..6{
-            for i in 0..subdivisions{
-                for j in 0..subdivisions{
-                    let (sx, sy) = (i as f32 / subdivisions as f32 * 2.0 - 1.0, j as f32 / subdivisions as f32 * 2.0 - 1.0);
-                    let pos = match s {
-                        0 => Position::new(1.0, sx, sy, 1.0),
-                        1 => Position::new(-1.0, sx, sy, 1.0),
-                        2 => Position::new(sx, 1.0, sy, 1.0),
-                        3 => Position::new(sx, -1.0, sy, 1.0),
-                        4 => Position::new(sx, sy, 1.0, 1.0),
-                        5 => Position::new(sx, sy, -1.0, 1.0),
-                        _ => unreachable!(),
-                    };
-                    
-                    vertices.push(Vertex {
-                        position: spherify(pos, radius) + origin,
-                        color: Color::blue(),
-                        normal: (0.0, 0.0, 1.0).into(),
+        // the normal, u and v axes of each face, with u cross v pointing out of the cube
+        let axes: [[[i32; 3]; 3]; 6] = [
+            [[1, 0, 0], [0, 0, -1], [0, 1, 0]],
+            [[-1, 0, 0], [0, 0, 1], [0, 1, 0]],
+            [[0, 1, 0], [1, 0, 0], [0, 0, -1]],
+            [[0, -1, 0], [1, 0, 0], [0, 0, 1]],
+            [[0, 0, 1], [1, 0, 0], [0, 1, 0]],
+            [[0, 0, -1], [-1, 0, 0], [0, 1, 0]],
+        ];
+
+        // vertices are keyed by their integer position on the cube lattice so edges are shared exactly
+        let mut lattice: HashMap<[i32; 3], u32> = HashMap::new();
+
+        for [normal, u, v] in axes {
+            let mut face = CubeFace {
+                normal,
+                resolution: n as u32,
+                vertices: Vec::new(),
+                uvs: Vec::new(),
+            };
+
+            for j in 0..=n {
+                for i in 0..=n {
+                    let (s, t) = (2 * i - n, 2 * j - n);
+                    let key = [
+                        normal[0] * n + u[0] * s + v[0] * t,
+                        normal[1] * n + u[1] * s + v[1] * t,
+                        normal[2] * n + u[2] * s + v[2] * t,
+                    ];
+
+                    let index = *lattice.entry(key).or_insert_with(|| {
+                        let pos = Position::new(key[0] as f32 / n as f32, key[1] as f32 / n as f32, key[2] as f32 / n as f32, 1.0);
+                        let position = spherify(pos, radius);
+
+                        vertices.push(Vertex {
+                            position: Position::new(position.x + origin.x, position.y + origin.y, position.z + origin.z, 1.0),
+                            color: Color::blue(),
+                            normal: Normal::new(position.x, position.y, position.z).normalize(),
+                        });
+
+                        Mesh::index_of(vertices.len() - 1)
                     });
 
-                    let index = (s * subdivisions * subdivisions) + (i * subdivisions) + j;
-                    // determine index of vertex
-                    if i < subdivisions - 1 && j < subdivisions - 1 {
-                        indices.extend_from_slice(&[
-                            index,
-                            index + subdivisions + 1,
-                            index + 1,
-                            index + 1,
-                            index + subdivisions + 1,
-                            index + subdivisions,
-                        ])
-                    }
-                    // else {
-                    //     indices.extend_from_slice(&[
-                    //         index + subdivisions,
-                    //         index,
-                    //         index + 1,
-                    //     ])
-                    // }
+                    face.vertices.push(index);
+                    face.uvs.push([i as f32 / n as f32, j as f32 / n as f32]);
                 }
             }
+
+            // two counter-clockwise triangles for every quad of the face
+            for j in 0..n as u32 {
+                for i in 0..n as u32 {
+                    let a = face.index(i, j);
+                    let b = face.index(i + 1, j);
+                    let c = face.index(i + 1, j + 1);
+                    let d = face.index(i, j + 1);
+
+                    indices.extend_from_slice(&[a, b, c, a, c, d]);
+                }
+            }
+
+            faces.push(face);
         }
 
-        Mesh::new(vertices, indices)
+        (Mesh::new(vertices, indices), faces)
     }
 
-    pub fn subdivide(&mut self, n_subdivisions: u32) {
+    // raise the resolution of the sphere, leaving it untouched when the result would not fit
+    // uv spheres and spherified cubes are rebuilt from their radius, origin and rotation, so vertices edited directly in the mesh are reset
+    pub fn subdivide(&mut self, n_subdivisions: u32) -> Result<(), SubdivisionError> {
         match self.sphere_type {
             Sphere::UVSphere => self.uv_subdivide(n_subdivisions),
             Sphere::Icosahedron => self.icosahedron_subdivide(n_subdivisions),
@@ -253,19 +367,198 @@ impl Spherical {
         }
     }
 
-    fn uv_subdivide(&self, _subdivisions: u32) {
-        return
+    // regenerate the uv sphere with twice the sectors and stacks per subdivision
+    fn uv_subdivide(&mut self, subdivisions: u32) -> Result<(), SubdivisionError> {
+        let sectors = SubdivisionError::doubled(self.sectors, subdivisions)?;
+        let stacks = SubdivisionError::doubled(self.stacks, subdivisions)?;
+
+        // a pole at each end and a ring of sectors between every pair of stacks
+        let vertices = (stacks.max(2) as u64 - 1) * sectors.max(3) as u64 + 2;
+        if vertices >= u32::MAX as u64 {
+            return Err(SubdivisionError::TooManyVertices { level: subdivisions });
+        }
+
+        self.sectors = sectors;
+        self.stacks = stacks;
+        self.generate();
+
+        Ok(())
     }
 
-    fn icosahedron_subdivide(&self, _subdivisions: u32) {
-        return
+    // split every triangle into four, pushing the new midpoints out onto the sphere
+    fn icosahedron_subdivide(&mut self, subdivisions: u32) -> Result<(), SubdivisionError> {
+        self.mesh.subdivision_fits(subdivisions)?;
+
+        for _ in 0..subdivisions {
+            self.mesh.subdivide(1)?;
+
+            for vertex in &mut self.mesh.vertices {
+                vertex.normal = Normal::from(vertex.position - self.origin).normalize();
+                vertex.position = self.origin + vertex.normal * self.radius;
+            }
+        }
+
+        self.mesh.update_bounds();
+
+        Ok(())
     }
 
-    fn spherified_cube_subdivide(&self, _subdivisions: u32) {
-        return
+    // regenerate the spherified cube with twice the resolution per subdivision
+    fn spherified_cube_subdivide(&mut self, subdivisions: u32) -> Result<(), SubdivisionError> {
+        let resolution = SubdivisionError::doubled(self.resolution, subdivisions)?;
+
+        // a grid on each of the six faces sharing its edges, which leaves eight corners and a face's worth of grid points over
+        let n = resolution.max(1) as u64;
+        if 6 * n * n + 2 >= u32::MAX as u64 {
+            return Err(SubdivisionError::TooManyVertices { level: subdivisions });
+        }
+
+        self.resolution = resolution;
+        self.generate();
+
+        Ok(())
     }
 
     fn dedup(&mut self) {
         self.mesh.dedup();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::Shape;
+    use crate::graphics::primitives::geometry::tests::{assert_outward_winding, assert_outward_unit_normals};
+
+    #[test]
+    fn test_uv_subdivide_overflow_leaves_the_sphere() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let mut sphere = Spherical::uv(1.0, origin, 8, 4);
+        let before = sphere.mesh.vertices.len();
+
+        assert_eq!(sphere.subdivide(32), Err(SubdivisionError::ResolutionOverflow { resolution: 8, level: 32 }));
+        assert!(matches!(sphere.subdivide(14), Err(SubdivisionError::TooManyVertices { .. })));
+        assert_eq!((sphere.sectors, sphere.stacks), (8, 4));
+        assert_eq!(sphere.mesh.vertices.len(), before);
+    }
+
+    #[test]
+    fn test_cube_sphere_subdivide_overflow_leaves_the_sphere() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let mut sphere = Spherical::cube_sphere(1.0, origin, 4);
+        let before = sphere.mesh.vertices.len();
+
+        assert_eq!(sphere.subdivide(31), Err(SubdivisionError::ResolutionOverflow { resolution: 4, level: 31 }));
+        assert!(matches!(sphere.subdivide(13), Err(SubdivisionError::TooManyVertices { .. })));
+        assert_eq!(sphere.resolution, 4);
+        assert_eq!(sphere.mesh.vertices.len(), before);
+    }
+
+    #[test]
+    fn test_cube_sphere_subdivide_keeps_rotation() {
+        let origin = Position::new(1.0, 2.0, 3.0, 1.0);
+        let mut sphere = Spherical::cube_sphere(1.0, origin, 2);
+        sphere.rotate(0.3, Vec3::Y);
+        sphere.subdivide(1).unwrap();
+
+        // the same as building at the finer resolution and rotating that
+        let mut expected = Spherical::cube_sphere(1.0, origin, 4);
+        expected.rotate(0.3, Vec3::Y);
+
+        assert_eq!(sphere.resolution, 4);
+        for (vertex, expected) in sphere.mesh.vertices.iter().zip(&expected.mesh.vertices) {
+            assert!(vertex.position.distance(expected.position) < 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_uv_subdivide_keeps_rotation() {
+        let origin = Position::new(1.0, 2.0, 3.0, 1.0);
+        let mut sphere = Spherical::uv(1.0, origin, 8, 4);
+        sphere.rotate(std::f32::consts::FRAC_PI_2, Vec3::X);
+        sphere.subdivide(1).unwrap();
+
+        // the pole starts on +z, a quarter turn about x carries it to -y
+        let pole = sphere.mesh.vertices[0].position;
+        assert!((pole.x - 1.0).abs() < 1e-5 && (pole.y - 1.0).abs() < 1e-5 && (pole.z - 3.0).abs() < 1e-5, "{}", pole);
+    }
+
+    #[test]
+    fn test_icosphere_subdivision_levels() {
+        let origin = Position::new(1.0, 2.0, 3.0, 1.0);
+        let radius = 2.5;
+
+        for level in 0..=6 {
+            let mut sphere = Geometry::new(origin, radius, Shape::Icosahedron);
+            sphere.subdivide(level).unwrap();
+            assert_outward_winding(sphere.mesh(), origin);
+
+            // every level splits each face in four and adds one vertex per edge
+            let faces = 20 * 4_usize.pow(level);
+            assert_eq!(sphere.index_len(), faces * 3);
+            assert_eq!(sphere.vertex_len(), faces / 2 + 2);
+
+            for vertex in sphere.vertices() {
+                assert!((vertex.position.distance(origin) - radius).abs() < 1e-4);
+            }
+
+            assert_outward_unit_normals(sphere.mesh(), origin);
+        }
+    }
+
+    #[test]
+    fn test_uv_sphere_resolution() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let mut sphere = Spherical::uv(1.0, origin, 16, 8);
+
+        // the poles are shared so only the rings between them repeat per sector
+        assert_eq!(sphere.mesh.vertices.len(), 2 + 7 * 16);
+        assert_eq!(sphere.mesh.indices.len(), 16 * 8 * 2 * 3 - 16 * 2 * 3);
+
+        let poles = sphere.mesh.vertices.iter()
+            .filter(|vertex| vertex.position.x.abs() < 1e-6 && vertex.position.y.abs() < 1e-6)
+            .count();
+        assert_eq!(poles, 2);
+
+        assert_outward_winding(&sphere.mesh, origin);
+        assert_outward_unit_normals(&sphere.mesh, origin);
+
+        sphere.subdivide(1).unwrap();
+        assert_eq!((sphere.sectors, sphere.stacks), (32, 16));
+        assert_eq!(sphere.mesh.vertices.len(), 2 + 15 * 32);
+        assert_outward_winding(&sphere.mesh, origin);
+    }
+
+    #[test]
+    fn test_cube_sphere_is_watertight() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let mut sphere = Spherical::cube_sphere(1.0, origin, 4);
+        sphere.subdivide(1).unwrap();
+
+        let n = sphere.resolution as usize;
+        assert_eq!(n, 8);
+        assert_eq!(sphere.mesh.vertices.len(), 6 * n * n + 2);
+
+        // a closed surface uses every edge exactly once in each direction
+        let mut edges = std::collections::HashMap::new();
+        for triangle in sphere.mesh.indices.chunks_exact(3) {
+            for k in 0..3 {
+                *edges.entry((triangle[k], triangle[(k + 1) % 3])).or_insert(0) += 1;
+            }
+        }
+        for ((a, b), count) in &edges {
+            assert_eq!(*count, 1);
+            assert_eq!(edges.get(&(*b, *a)), Some(&1));
+        }
+
+        assert_outward_winding(&sphere.mesh, origin);
+        assert_outward_unit_normals(&sphere.mesh, origin);
+
+        // every face covers the full uv square
+        assert_eq!(sphere.faces.len(), 6);
+        for face in &sphere.faces {
+            assert_eq!(face.uv(0, 0), [0.0, 0.0]);
+            assert_eq!(face.uv(n as u32, n as u32), [1.0, 1.0]);
+        }
+    }
+}
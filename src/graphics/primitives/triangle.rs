@@ -4,6 +4,7 @@ use crate::graphics::Position;
 use crate::graphics::Color;
 use crate::graphics::Normal;
 use crate::graphics::Geometry;
+use crate::graphics::SubdivisionError;
 
 #[derive(Debug, Clone)]
 pub struct Triangle {
@@ -60,8 +61,34 @@ impl Triangle {
         Mesh::new(vertices, vec![0, 1, 2])
     }
 
-    // subdivide the triangle
-    pub fn subdivide(&mut self, _iterations: u32) {
-        return;
+    // subdivide the triangle into four smaller triangles per iteration
+    pub fn subdivide(&mut self, iterations: u32) -> Result<(), SubdivisionError> {
+        self.mesh.subdivide(iterations)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triangle_subdivision_shares_midpoints() {
+        let origin = Position::new(0.0, 0.0, 0.0, 1.0);
+        let mut triangle = Triangle::new(origin, 1.0);
+        triangle.subdivide(2).unwrap();
+
+        // each iteration quadruples the faces and adds one vertex per edge
+        assert_eq!(triangle.index_len(), 16 * 3);
+        assert_eq!(triangle.vertex_len(), 15);
+
+        // the winding of every new face still faces +z
+        for face in triangle.mesh().indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|k| triangle.vertices()[face[k] as usize].position);
+            assert!(Mesh::normalize(a, b, c)[2] > 0.99);
+        }
+
+        for vertex in triangle.vertices() {
+            assert!((vertex.normal.length() - 1.0).abs() < 1e-5);
+        }
+    }
+}
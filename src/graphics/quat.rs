@@ -0,0 +1,236 @@
+use crate::graphics::position::Position;
+use crate::graphics::normal::Normal;
+use crate::graphics::mat4::Mat4;
+
+// unit quaternion orientation, composed in the same order as Mat4 so a * b rotates by a and then by b
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Quat {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+    pub w: f32,
+}
+
+impl Quat {
+    pub const IDENTITY: Quat = Quat { x: 0.0, y: 0.0, z: 0.0, w: 1.0 };
+
+    pub fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+
+    pub fn identity() -> Self {
+        Self::IDENTITY
+    }
+
+    // counter-clockwise rotation in radians looking down the axis towards the origin
+    pub fn from_axis_angle(axis: Normal, angle: f32) -> Self {
+        let axis = axis.normalize();
+        let (sin, cos) = (angle / 2.0).sin_cos();
+
+        Self::new(axis[0] * sin, axis[1] * sin, axis[2] * sin, cos)
+    }
+
+    // yaw around y, pitch around x and roll around z, applied roll first and yaw last
+    pub fn from_euler(yaw: f32, pitch: f32, roll: f32) -> Self {
+        let yaw = Self::from_axis_angle(Normal::new(0.0, 1.0, 0.0), yaw);
+        let pitch = Self::from_axis_angle(Normal::new(1.0, 0.0, 0.0), pitch);
+        let roll = Self::from_axis_angle(Normal::new(0.0, 0.0, 1.0), roll);
+
+        roll * pitch * yaw
+    }
+
+    // yaw, pitch and roll that from_euler turns back into this rotation
+    pub fn to_euler(&self) -> (f32, f32, f32) {
+        let m = Mat4::from_quat(*self);
+
+        let pitch = (-m[2][1]).clamp(-1.0, 1.0).asin();
+        let yaw = m[2][0].atan2(m[2][2]);
+        let roll = m[0][1].atan2(m[1][1]);
+
+        (yaw, pitch, roll)
+    }
+
+    // rotation held by the upper 3x3 of a transform without scale
+    pub fn from_mat4(matrix: &Mat4) -> Self {
+        // r is the column vector form, the transpose of the row-vector matrix
+        let r = |row: usize, column: usize| matrix[column][row];
+        let trace = r(0, 0) + r(1, 1) + r(2, 2);
+
+        let quat = if trace > 0.0 {
+            let s = (trace + 1.0).sqrt() * 2.0;
+            Self::new((r(2, 1) - r(1, 2)) / s, (r(0, 2) - r(2, 0)) / s, (r(1, 0) - r(0, 1)) / s, s / 4.0)
+        } else if r(0, 0) > r(1, 1) && r(0, 0) > r(2, 2) {
+            let s = (1.0 + r(0, 0) - r(1, 1) - r(2, 2)).sqrt() * 2.0;
+            Self::new(s / 4.0, (r(0, 1) + r(1, 0)) / s, (r(0, 2) + r(2, 0)) / s, (r(2, 1) - r(1, 2)) / s)
+        } else if r(1, 1) > r(2, 2) {
+            let s = (1.0 + r(1, 1) - r(0, 0) - r(2, 2)).sqrt() * 2.0;
+            Self::new((r(0, 1) + r(1, 0)) / s, s / 4.0, (r(1, 2) + r(2, 1)) / s, (r(0, 2) - r(2, 0)) / s)
+        } else {
+            let s = (1.0 + r(2, 2) - r(0, 0) - r(1, 1)).sqrt() * 2.0;
+            Self::new((r(0, 2) + r(2, 0)) / s, (r(1, 2) + r(2, 1)) / s, s / 4.0, (r(1, 0) - r(0, 1)) / s)
+        };
+
+        quat.normalize()
+    }
+
+    pub fn dot(self, target: Quat) -> f32 {
+        self.x * target.x + self.y * target.y + self.z * target.z + self.w * target.w
+    }
+
+    pub fn length(self) -> f32 {
+        self.dot(self).sqrt()
+    }
+
+    // scale to unit length, falling back to the identity for a zero quaternion
+    pub fn normalize(self) -> Quat {
+        let length = self.length();
+
+        if length == 0.0 {
+            return Self::IDENTITY;
+        }
+
+        Self::new(self.x / length, self.y / length, self.z / length, self.w / length)
+    }
+
+    pub fn conjugate(self) -> Quat {
+        Self::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    // the opposite rotation
+    pub fn inverse(self) -> Quat {
+        let length_squared = self.dot(self);
+
+        if length_squared == 0.0 {
+            return Self::IDENTITY;
+        }
+
+        let conjugate = self.conjugate();
+        Self::new(conjugate.x / length_squared, conjugate.y / length_squared, conjugate.z / length_squared, conjugate.w / length_squared)
+    }
+
+    // rotate a direction
+    pub fn rotate(self, vector: Normal) -> Normal {
+        let axis = Normal::new(self.x, self.y, self.z);
+        let t = axis.cross(vector) * 2.0;
+
+        vector + t * self.w + axis.cross(t)
+    }
+
+    // rotate a point around the origin, keeping its w
+    pub fn rotate_position(self, position: Position) -> Position {
+        let rotated = self.rotate(Normal::new(position.x, position.y, position.z));
+
+        Position::new(rotated[0], rotated[1], rotated[2], position.w)
+    }
+
+    // spherical interpolation along the shortest arc
+    pub fn slerp(self, target: Quat, t: f32) -> Quat {
+        let mut target = target;
+        let mut dot = self.dot(target);
+
+        // q and -q are the same rotation, going the other way round is shorter
+        if dot < 0.0 {
+            target = Self::new(-target.x, -target.y, -target.z, -target.w);
+            dot = -dot;
+        }
+
+        // nearly parallel, a straight line is accurate and avoids dividing by sin of a tiny angle
+        if dot > 0.9995 {
+            return Self::new(
+                self.x + (target.x - self.x) * t,
+                self.y + (target.y - self.y) * t,
+                self.z + (target.z - self.z) * t,
+                self.w + (target.w - self.w) * t,
+            ).normalize();
+        }
+
+        let theta = dot.acos();
+        let sin = theta.sin();
+        let a = ((1.0 - t) * theta).sin() / sin;
+        let b = (t * theta).sin() / sin;
+
+        Self::new(
+            self.x * a + target.x * b,
+            self.y * a + target.y * b,
+            self.z * a + target.z * b,
+            self.w * a + target.w * b,
+        )
+    }
+
+    pub fn to_mat4(self) -> Mat4 {
+        Mat4::from_quat(self)
+    }
+}
+
+impl Default for Quat {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+// rotate by self and then by rhs
+impl std::ops::Mul for Quat {
+    type Output = Quat;
+
+    fn mul(self, rhs: Quat) -> Quat {
+        // hamilton product rhs * self
+        let (a, b) = (rhs, self);
+
+        Quat::new(
+            a.w * b.x + a.x * b.w + a.y * b.z - a.z * b.y,
+            a.w * b.y - a.x * b.z + a.y * b.w + a.z * b.x,
+            a.w * b.z + a.x * b.y - a.y * b.x + a.z * b.w,
+            a.w * b.w - a.x * b.x - a.y * b.y - a.z * b.z,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Normal, b: Normal) -> bool {
+        (a - b).length() < 1e-5
+    }
+
+    #[test]
+    fn composes_like_matrices() {
+        let a = Quat::from_axis_angle(Normal::new(1.0, 2.0, 0.5), 0.8);
+        let b = Quat::from_euler(1.2, -0.4, 0.3);
+        let vector = Normal::new(0.3, -2.0, 1.0);
+
+        let by_quat = (a * b).rotate(vector);
+        let by_matrix = (Mat4::from_quat(a) * Mat4::from_quat(b)).transform_vector(vector);
+
+        assert!(close(by_quat, by_matrix));
+        assert!(close(b.rotate(a.rotate(vector)), by_quat));
+        assert!(close((a * a.inverse()).rotate(vector), vector));
+    }
+
+    #[test]
+    fn euler_and_matrix_round_trip() {
+        let (yaw, pitch, roll) = Quat::from_euler(0.7, -0.5, 2.1).to_euler();
+
+        assert!((yaw - 0.7).abs() < 1e-5);
+        assert!((pitch + 0.5).abs() < 1e-5);
+        assert!((roll - 2.1).abs() < 1e-5);
+
+        let rotation = Quat::from_euler(-2.5, 1.0, 0.2);
+        let recovered = Quat::from_mat4(&rotation.to_mat4());
+        assert!(rotation.dot(recovered).abs() > 1.0 - 1e-5);
+    }
+
+    #[test]
+    fn slerp_follows_the_arc() {
+        let start = Quat::IDENTITY;
+        let end = Quat::from_axis_angle(Normal::new(0.0, 1.0, 0.0), std::f32::consts::FRAC_PI_2);
+
+        let halfway = start.slerp(end, 0.5);
+        let expected = Quat::from_axis_angle(Normal::new(0.0, 1.0, 0.0), std::f32::consts::FRAC_PI_4);
+
+        assert!(halfway.dot(expected).abs() > 1.0 - 1e-6);
+        assert!((halfway.length() - 1.0).abs() < 1e-6);
+        assert_eq!(start.slerp(end, 0.0), start);
+    }
+}
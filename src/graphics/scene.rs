@@ -0,0 +1,736 @@
+use std::collections::HashMap;
+use crate::graphics::{Mesh, Topology, Coloring, DebugView, DebugDraw, WIREFRAME_COLOR, NORMAL_COLOR};
+use crate::graphics::{GrowableBuffer, BufferPool};
+use crate::graphics::{Instance, InstancedMesh, InstancedMeshId};
+use crate::graphics::Frustum;
+use crate::graphics::Vertex;
+use crate::graphics::{Position, Vec3};
+use crate::graphics::Geometry;
+use crate::graphics::{Mat4, Quat, Transform};
+use crate::types::{WorldPosition, FloatingOrigin};
+
+// everything about how an object is rasterized that needs its own pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineKey {
+    pub topology: Topology,
+    pub cull_mode: Option<wgpu::Face>,
+    pub polygon_mode: wgpu::PolygonMode,
+    // strips need to know which index restarts them
+    pub strip_index_format: Option<wgpu::IndexFormat>,
+    // instanced meshes read a transform and tint per instance from a second vertex buffer
+    pub instanced: bool,
+    // overlays pass the depth test against surfaces at the same depth and leave the depth buffer alone
+    pub overlay: bool,
+}
+
+impl PipelineKey {
+    pub fn new(topology: Topology, cull_mode: Option<wgpu::Face>) -> Self {
+        Self {
+            topology,
+            cull_mode,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            strip_index_format: None,
+            instanced: false,
+            overlay: false,
+        }
+    }
+
+    // lines drawn over the top of the scene
+    pub fn overlay(topology: Topology, polygon_mode: wgpu::PolygonMode) -> Self {
+        Self {
+            topology,
+            cull_mode: None,
+            polygon_mode,
+            strip_index_format: None,
+            instanced: false,
+            overlay: true,
+        }
+    }
+}
+
+// handle used to look up an object after it has been added to the scene
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObjectId(pub u32);
+
+// model matrix of an object as the shader sees it, with the matrix used to carry its normals
+#[repr(C)]
+#[derive(Debug, Copy, Clone, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct ModelUniform {
+    pub model: Mat4,
+    pub normal: Mat4,
+}
+
+impl ModelUniform {
+    pub fn new(model: Mat4) -> Self {
+        Self {
+            model,
+            normal: model.normal_matrix(),
+        }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(Mat4::IDENTITY)
+    }
+}
+
+// matrix rotating positions around an axis through the origin, matching Position::rotate
+pub fn rotation_about(angle: f32, origin: Position, axis: Vec3) -> Mat4 {
+    Mat4::translation(-origin) * Mat4::rotation(axis.into(), angle) * Mat4::translation(origin)
+}
+
+// a single renderable mesh placed by a transform relative to its parent
+#[derive(Debug)]
+pub struct Object {
+    pub id: ObjectId,
+    // vertices stay in mesh space, the transform is applied by the shader
+    pub mesh: Mesh,
+    // point in mesh space the object rotates and scales around
+    pub origin: Position,
+    pub visible: bool,
+    // set when the object was outside the frustum this frame
+    pub culled: bool,
+    pub cull_mode: Option<wgpu::Face>,
+    pub coloring: Coloring,
+    pub vertex_buffer: GrowableBuffer,
+    pub index_buffer: GrowableBuffer,
+    pub index_format: wgpu::IndexFormat,
+    pub n_indices: u32,
+    transform: Transform,
+    parent: Option<ObjectId>,
+    // where in the open world a root object is, its translation is worked out from this whenever the render origin moves
+    // setting the translation or the whole transform forgets it, so whichever was set last wins
+    world_position: Option<WorldPosition>,
+    // local transform combined with every parent's, recomputed by the scene when dirty
+    world: Mat4,
+    dirty: bool,
+}
+
+impl Object {
+    // create an object with buffers from the pool and upload its mesh
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, pool: &mut BufferPool, id: ObjectId, mesh: Mesh, origin: Position) -> Self {
+        let vertex_size = (mesh.vertices.len() * std::mem::size_of::<Vertex>()) as wgpu::BufferAddress;
+        let index_size = mesh.index_bytes().len() as wgpu::BufferAddress;
+        let cull_mode = mesh.topology.default_cull_mode();
+        let index_format = mesh.index_format();
+
+        let mut object = Self {
+            id,
+            mesh,
+            origin,
+            visible: true,
+            culled: false,
+            cull_mode,
+            coloring: Coloring::None,
+            vertex_buffer: pool.take("Vertex Buffer", wgpu::BufferUsages::VERTEX, vertex_size),
+            index_buffer: pool.take("Index Buffer", wgpu::BufferUsages::INDEX, index_size),
+            index_format,
+            n_indices: 0,
+            transform: Transform::IDENTITY,
+            parent: None,
+            world_position: None,
+            world: Mat4::IDENTITY,
+            dirty: false,
+        };
+        object.upload(device, queue);
+
+        object
+    }
+
+    // copy of a mesh with the transform applied to each vertex and normal
+    fn transformed(mesh: &Mesh, transform: Mat4) -> Mesh {
+        let normal = transform.normal_matrix();
+        let vertices: Vec<Vertex> = mesh.vertices.iter()
+            .map(|vertex| Vertex::new(
+                vertex.position * transform,
+                vertex.color,
+                (vertex.normal.to_vec4() * normal).into(),
+            ))
+            .collect();
+
+        Mesh::with_topology(vertices, mesh.indices.clone(), mesh.topology)
+    }
+
+    // the mesh as it is drawn, with the world transform applied
+    pub fn world_mesh(&self) -> Mesh {
+        Self::transformed(&self.mesh, self.world)
+    }
+
+    // write the mesh into the buffers again after its vertices or coloring changed
+    pub fn upload(&mut self, device: &wgpu::Device, queue: &wgpu::Queue) {
+        let mesh = self.mesh.debug_colored(self.coloring);
+
+        self.vertex_buffer.write(device, queue, bytemuck::cast_slice(&mesh.vertices));
+        self.index_buffer.write(device, queue, &mesh.index_bytes());
+        self.index_format = mesh.index_format();
+        self.n_indices = mesh.indices.len() as u32;
+    }
+
+    // pipeline this object is drawn with
+    pub fn pipeline_key(&self) -> PipelineKey {
+        PipelineKey {
+            strip_index_format: if self.mesh.topology.is_strip() { Some(self.index_format) } else { None },
+            ..PipelineKey::new(self.mesh.topology, self.cull_mode)
+        }
+    }
+
+    // whether any of the transformed mesh could be seen, testing the cheap sphere before the box
+    pub fn in_view(&self, frustum: &Frustum) -> bool {
+        frustum.intersects_sphere(&self.mesh.bounding_sphere.transformed(self.world))
+            && frustum.intersects_aabb(&self.mesh.aabb.transformed(self.world))
+    }
+
+    pub fn transform(&self) -> &Transform {
+        &self.transform
+    }
+
+    // change the transform, the world matrix follows on the next update
+    pub fn transform_mut(&mut self) -> &mut Transform {
+        self.dirty = true;
+        &mut self.transform
+    }
+
+    // a translation written through transform_mut is replaced on the next rebase while the object has a world position
+    pub fn set_transform(&mut self, transform: Transform) {
+        self.world_position = None;
+        *self.transform_mut() = transform;
+    }
+
+    pub fn set_translation(&mut self, translation: Position) {
+        self.world_position = None;
+        self.transform_mut().translation = translation;
+    }
+
+    // rotate the object around its own origin, after the rotation it already has
+    pub fn rotate(&mut self, angle: f32, axis: Vec3) {
+        self.transform_mut().rotate(Quat::from_axis_angle(axis.into(), angle));
+    }
+
+    pub fn parent(&self) -> Option<ObjectId> {
+        self.parent
+    }
+
+    // where in the open world the object was placed, None unless it is a root placed with set_world_position
+    pub fn world_position(&self) -> Option<WorldPosition> {
+        self.world_position
+    }
+
+    // place a root object in the open world, drawn relative to the render origin
+    // a child is placed by its parent, so it is left alone and false returned
+    pub fn set_world_position(&mut self, position: WorldPosition, origin: &FloatingOrigin) -> bool {
+        if self.parent.is_some() {
+            return false;
+        }
+
+        self.transform_mut().translation = origin.to_render(position);
+        self.world_position = Some(position);
+
+        true
+    }
+
+    // move the translation from under the previous render origin to under the current one
+    fn rebase(&mut self, previous: &FloatingOrigin, current: &FloatingOrigin) {
+        let translation = match self.world_position {
+            Some(position) => current.to_render(position),
+            None => current.carry(self.transform.translation, previous),
+        };
+
+        self.transform_mut().translation = translation;
+    }
+
+    pub fn local_matrix(&self) -> Mat4 {
+        Mat4::translation(-self.origin) * self.transform.matrix() * Mat4::translation(self.origin)
+    }
+
+    // the transform whose local matrix around an origin is the given matrix
+    fn transform_for(origin: Position, local: Mat4) -> Transform {
+        Transform::from_matrix(Mat4::translation(origin) * local * Mat4::translation(-origin))
+    }
+
+    // the transform the shader applies to the mesh, as of the last scene update
+    pub fn world_matrix(&self) -> Mat4 {
+        self.world
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+}
+
+// how much of the scene the last cull left out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CullStats {
+    pub objects: u32,
+    pub objects_culled: u32,
+    pub instances: u32,
+    pub instances_culled: u32,
+}
+
+impl CullStats {
+    pub fn objects_drawn(&self) -> u32 {
+        self.objects - self.objects_culled
+    }
+
+    pub fn instances_drawn(&self) -> u32 {
+        self.instances - self.instances_culled
+    }
+}
+
+// collection of every object drawn by the renderer
+#[derive(Debug)]
+pub struct Scene {
+    pub objects: Vec<Object>,
+    pub instanced: Vec<InstancedMesh>,
+    // skip objects and instances outside the camera's view
+    pub culling: bool,
+    pub cull_stats: CullStats,
+    next_id: u32,
+    coloring: Coloring,
+    pool: BufferPool,
+    // one model uniform per object, each starting on a dynamic offset boundary
+    models: GrowableBuffer,
+    model_stride: wgpu::BufferAddress,
+    model_bind_group: Option<wgpu::BindGroup>,
+}
+
+impl Scene {
+    pub fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+            instanced: Vec::new(),
+            culling: true,
+            cull_stats: CullStats::default(),
+            next_id: 0,
+            coloring: Coloring::None,
+            pool: BufferPool::new(),
+            models: GrowableBuffer::new("Model Buffer", wgpu::BufferUsages::UNIFORM),
+            model_stride: 0,
+            model_bind_group: None,
+        }
+    }
+
+    // add a mesh rotating around the given origin
+    pub fn add(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, mesh: Mesh, origin: Position) -> ObjectId {
+        let id = ObjectId(self.next_id);
+        self.next_id += 1;
+
+        let mut object = Object::new(device, queue, &mut self.pool, id, mesh, origin);
+        if self.coloring != Coloring::None {
+            object.coloring = self.coloring;
+            object.upload(device, queue);
+        }
+        self.objects.push(object);
+
+        id
+    }
+
+    // add a primitive using its mesh and origin
+    pub fn add_geometry(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, geometry: Geometry) -> ObjectId {
+        self.add(device, queue, geometry.mesh().clone(), geometry.origin())
+    }
+
+    // remove an object, returning its mesh and keeping its buffers for the next object added
+    pub fn remove(&mut self, id: ObjectId) -> Option<Mesh> {
+        let index = self.objects.iter().position(|object| object.id == id)?;
+
+        // worked out from the transforms rather than the last update, which may not have seen recent changes
+        let world = self.ancestors(id)
+            .filter_map(|ancestor| self.get(ancestor))
+            .fold(Mat4::IDENTITY, |world, ancestor| world * ancestor.local_matrix());
+        let object = self.objects.remove(index);
+
+        // children take on the parent's world transform so they stay where they were drawn
+        for child in self.objects.iter_mut().filter(|child| child.parent == Some(id)) {
+            child.transform = Object::transform_for(child.origin, child.local_matrix() * world);
+            child.parent = None;
+            child.dirty = true;
+        }
+
+        self.pool.give(object.vertex_buffer);
+        self.pool.give(object.index_buffer);
+
+        Some(object.mesh)
+    }
+
+    // add a mesh drawn once for every instance
+    pub fn add_instanced(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, mesh: Mesh, instances: Vec<Instance>) -> InstancedMeshId {
+        let id = InstancedMeshId(self.next_id);
+        self.next_id += 1;
+
+        self.instanced.push(InstancedMesh::new(device, queue, &mut self.pool, id, mesh, instances));
+
+        id
+    }
+
+    // remove an instanced mesh, returning its mesh and keeping its buffers for the next mesh added
+    pub fn remove_instanced(&mut self, id: InstancedMeshId) -> Option<Mesh> {
+        let index = self.instanced.iter().position(|instanced| instanced.id == id)?;
+        let instanced = self.instanced.remove(index);
+
+        self.pool.give(instanced.vertex_buffer);
+        self.pool.give(instanced.index_buffer);
+        self.pool.give(instanced.instance_buffer);
+
+        Some(instanced.mesh)
+    }
+
+    pub fn instanced(&self, id: InstancedMeshId) -> Option<&InstancedMesh> {
+        self.instanced.iter().find(|instanced| instanced.id == id)
+    }
+
+    pub fn instanced_mut(&mut self, id: InstancedMeshId) -> Option<&mut InstancedMesh> {
+        self.instanced.iter_mut().find(|instanced| instanced.id == id)
+    }
+
+    pub fn get(&self, id: ObjectId) -> Option<&Object> {
+        self.objects.iter().find(|object| object.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: ObjectId) -> Option<&mut Object> {
+        self.objects.iter_mut().find(|object| object.id == id)
+    }
+
+    // attach an object to a parent so it inherits the parent's world transform, or detach it with None
+    // fails when either object is missing or the parent is the object or one of its descendants
+    pub fn set_parent(&mut self, child: ObjectId, parent: Option<ObjectId>) -> bool {
+        if let Some(parent) = parent {
+            if self.get(parent).is_none() || self.ancestors(parent).any(|ancestor| ancestor == child) {
+                return false;
+            }
+        }
+
+        let Some(object) = self.get_mut(child) else { return false };
+        object.parent = parent;
+        object.dirty = true;
+        // only root objects keep a place in the open world
+        if parent.is_some() {
+            object.world_position = None;
+        }
+
+        true
+    }
+
+    // the object itself followed by its parent, its parent's parent and so on
+    pub fn ancestors(&self, id: ObjectId) -> impl Iterator<Item = ObjectId> + '_ {
+        std::iter::successors(self.get(id).map(|object| object.id), |id| self.get(*id).and_then(Object::parent))
+    }
+
+    pub fn children(&self, id: ObjectId) -> impl Iterator<Item = ObjectId> + '_ {
+        self.objects.iter().filter(move |object| object.parent == Some(id)).map(|object| object.id)
+    }
+
+    // recompute the world matrix of every object whose transform or any of whose parents' transforms changed
+    pub fn update_transforms(&mut self) {
+        let indices: HashMap<ObjectId, usize> = self.objects.iter().enumerate().map(|(index, object)| (object.id, index)).collect();
+        let mut visited = vec![false; self.objects.len()];
+        let mut changed = vec![false; self.objects.len()];
+
+        for index in 0..self.objects.len() {
+            self.update_world(index, &indices, &mut visited, &mut changed);
+        }
+    }
+
+    // update the parent first so a child always combines with the parent's latest world matrix
+    fn update_world(&mut self, index: usize, indices: &HashMap<ObjectId, usize>, visited: &mut [bool], changed: &mut [bool]) {
+        if visited[index] {
+            return;
+        }
+        visited[index] = true;
+
+        let parent = self.objects[index].parent.and_then(|parent| indices.get(&parent).copied());
+        let (parent_world, parent_changed) = match parent {
+            Some(parent) => {
+                self.update_world(parent, indices, visited, changed);
+                (self.objects[parent].world, changed[parent])
+            }
+            None => (Mat4::IDENTITY, false),
+        };
+
+        let object = &mut self.objects[index];
+        if object.dirty || parent_changed {
+            object.world = object.local_matrix() * parent_world;
+            object.dirty = false;
+            changed[index] = true;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        for object in self.objects.drain(..) {
+            self.pool.give(object.vertex_buffer);
+            self.pool.give(object.index_buffer);
+        }
+
+        for instanced in self.instanced.drain(..) {
+            self.pool.give(instanced.vertex_buffer);
+            self.pool.give(instanced.index_buffer);
+            self.pool.give(instanced.instance_buffer);
+        }
+    }
+
+    // rotate every object without a parent around its own origin, children turn with their parents
+    pub fn rotate(&mut self, angle: f32, axis: Vec3) {
+        for object in self.objects.iter_mut().filter(|object| object.parent.is_none()) {
+            object.rotate(angle, axis);
+        }
+    }
+
+    // place everything again under the current render origin after it moved, children follow their parents
+    pub fn rebase(&mut self, previous: &FloatingOrigin, current: &FloatingOrigin) {
+        for object in self.objects.iter_mut().filter(|object| object.parent.is_none()) {
+            object.rebase(previous, current);
+        }
+
+        for instanced in &mut self.instanced {
+            for instance in &mut instanced.instances {
+                instance.rebase(previous, current);
+            }
+        }
+    }
+
+    // mark what is outside the frustum so it is not drawn this frame
+    pub fn cull(&mut self, frustum: &Frustum) -> CullStats {
+        let frustum = if self.culling { Some(frustum) } else { None };
+        let mut stats = CullStats::default();
+
+        for object in self.objects.iter_mut().filter(|object| object.visible) {
+            object.culled = frustum.map(|frustum| !object.in_view(frustum)).unwrap_or(false);
+
+            stats.objects += 1;
+            stats.objects_culled += object.culled as u32;
+        }
+
+        for instanced in self.instanced.iter_mut().filter(|instanced| instanced.visible) {
+            stats.instances += instanced.instances.len() as u32;
+            stats.instances_culled += instanced.cull(frustum);
+        }
+
+        self.cull_stats = stats;
+
+        stats
+    }
+
+    // write the model matrix of every object and the instances of every instanced mesh, rebinding the model buffer if it had to grow
+    pub fn prepare(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, layout: &wgpu::BindGroupLayout) {
+        for instanced in &mut self.instanced {
+            instanced.upload_instances(device, queue);
+        }
+
+        let size = std::mem::size_of::<ModelUniform>();
+        let alignment = device.limits().min_uniform_buffer_offset_alignment as usize;
+        let stride = size.div_ceil(alignment) * alignment;
+
+        let mut contents = vec![0u8; stride * self.objects.len()];
+        for (object, slot) in self.objects.iter().zip(contents.chunks_exact_mut(stride)) {
+            slot[..size].copy_from_slice(bytemuck::bytes_of(&ModelUniform::new(object.world)));
+        }
+
+        let grown = self.models.write(device, queue, &contents);
+        self.model_stride = stride as wgpu::BufferAddress;
+
+        if grown || self.model_bind_group.is_none() {
+            let Some(buffer) = self.models.buffer() else { return };
+
+            self.model_bind_group = Some(device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Model Bind Group"),
+                layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                            buffer,
+                            offset: 0,
+                            size: wgpu::BufferSize::new(size as u64),
+                        }),
+                    }
+                ],
+            }));
+        }
+    }
+
+    // bind the model matrix written for the object at an index
+    fn bind_model<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, index: usize) -> bool {
+        let Some(bind_group) = &self.model_bind_group else { return false };
+        let offset = index as wgpu::BufferAddress * self.model_stride;
+        if offset + self.model_stride > self.models.len() {
+            return false;
+        }
+
+        render_pass.set_bind_group(2, bind_group, &[offset as wgpu::DynamicOffset]);
+
+        true
+    }
+
+    // pipelines needed to draw every object in the scene
+    pub fn pipeline_keys(&self) -> Vec<PipelineKey> {
+        let mut keys: Vec<PipelineKey> = Vec::new();
+
+        let objects = self.objects.iter().map(Object::pipeline_key);
+        let instanced = self.instanced.iter().map(InstancedMesh::pipeline_key);
+
+        for key in objects.chain(instanced) {
+            if !keys.contains(&key) {
+                keys.push(key);
+            }
+        }
+
+        keys
+    }
+
+    // recolor every object and write the new vertices
+    pub fn set_coloring(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, coloring: Coloring) {
+        self.coloring = coloring;
+
+        for object in &mut self.objects {
+            object.coloring = coloring;
+            object.upload(device, queue);
+        }
+    }
+
+    // draw the normals of everything visible, and the edges of objects when the wireframe has to be drawn as lines
+    // instances are never outlined by draw_wireframe, so their edges are always drawn here while the wireframe is on
+    pub fn debug_lines(&self, view: &DebugView, edges: bool, debug_draw: &mut DebugDraw) {
+        let mut draw = |mesh: &Mesh, transform: Mat4, edges: bool| {
+            if edges {
+                debug_draw.wireframe(mesh, transform, WIREFRAME_COLOR);
+            }
+            if view.normals {
+                debug_draw.normals(mesh, transform, view.normal_length, NORMAL_COLOR);
+            }
+        };
+
+        for object in self.objects.iter().filter(|object| object.visible && !object.culled) {
+            draw(&object.mesh, object.world, edges);
+        }
+
+        for instanced in self.instanced.iter().filter(|instanced| instanced.visible) {
+            for instance in instanced.instances_in_view() {
+                draw(&instanced.mesh, instance.transform, view.wireframe);
+            }
+        }
+    }
+
+    // draw the triangles of every visible object again as outlines
+    pub fn draw_wireframe<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipelines: &'a HashMap<PipelineKey, wgpu::RenderPipeline>) {
+        let key = PipelineKey::overlay(Topology::TriangleList, wgpu::PolygonMode::Line);
+        let Some(pipeline) = pipelines.get(&key) else { return };
+        render_pass.set_pipeline(pipeline);
+
+        for (index, object) in self.objects.iter().enumerate() {
+            if !object.visible || object.culled || object.n_indices == 0 || object.mesh.topology != Topology::TriangleList {
+                continue;
+            }
+
+            let (Some(vertices), Some(indices)) = (object.vertex_buffer.slice(), object.index_buffer.slice()) else { continue };
+            if !self.bind_model(render_pass, index) {
+                continue;
+            }
+
+            render_pass.set_vertex_buffer(0, vertices);
+            render_pass.set_index_buffer(indices, object.index_format);
+            render_pass.draw_indexed(0..object.n_indices, 0, 0..1);
+        }
+    }
+
+    // record draw calls for every visible object, switching pipelines only when the topology or cull mode changes
+    pub fn draw<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipelines: &'a HashMap<PipelineKey, wgpu::RenderPipeline>) {
+        let mut current: Option<PipelineKey> = None;
+
+        for (index, object) in self.objects.iter().enumerate() {
+            if !object.visible || object.culled || object.n_indices == 0 {
+                continue;
+            }
+
+            let (Some(vertices), Some(indices)) = (object.vertex_buffer.slice(), object.index_buffer.slice()) else { continue };
+            if !self.bind_model(render_pass, index) {
+                continue;
+            }
+
+            let key = object.pipeline_key();
+            if current != Some(key) {
+                let Some(pipeline) = pipelines.get(&key) else { continue };
+                render_pass.set_pipeline(pipeline);
+                current = Some(key);
+            }
+
+            render_pass.set_vertex_buffer(0, vertices);
+            render_pass.set_index_buffer(indices, object.index_format);
+            render_pass.draw_indexed(0..object.n_indices, 0, 0..1);
+        }
+    }
+
+    // record one draw call per instanced mesh, expects a model bind group to be set even though the instances carry their own transforms
+    pub fn draw_instanced<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, pipelines: &'a HashMap<PipelineKey, wgpu::RenderPipeline>) {
+        for instanced in &self.instanced {
+            let Some(pipeline) = pipelines.get(&instanced.pipeline_key()) else { continue };
+
+            render_pass.set_pipeline(pipeline);
+            instanced.draw(render_pass);
+        }
+    }
+}
+
+impl Default for Scene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::graphics::Normal;
+
+    fn close(a: Position, b: Position) -> bool {
+        a.distance(b) < 1e-5 && (a.w - b.w).abs() < 1e-5
+    }
+
+    #[test]
+    fn rotation_matrix_matches_rotating_vertices() {
+        let origin = Position::new(1.0, 2.0, 3.0, 1.0);
+        let axis = Vec3::Y;
+        let matrix = rotation_about(0.7, origin, axis);
+
+        for position in [Position::new(0.0, 0.0, 0.0, 1.0), Position::new(4.0, -1.0, 2.5, 1.0)] {
+            assert!(close(position * matrix, position.rotate(0.7, origin, axis)));
+        }
+    }
+
+    #[test]
+    fn normal_matrix_keeps_normals_perpendicular_under_scale() {
+        let mut model = Mat4::IDENTITY;
+        model[0][0] = 4.0;
+        model[3] = [5.0, 0.0, 0.0, 1.0];
+        let uniform = ModelUniform::new(model);
+
+        // a slanted surface and its normal, both through the transform
+        let along = Position::new(1.0, 1.0, 0.0, 0.0);
+        let normal = Position::new(1.0, -1.0, 0.0, 0.0);
+        let along = along * uniform.model;
+        let normal = normal * uniform.normal;
+
+        assert!(along.dot(normal).abs() < 1e-5);
+        assert_eq!(normal.w, 0.0);
+    }
+
+    #[test]
+    fn detached_transform_keeps_the_world_matrix() {
+        let origin = Position::new(0.5, -1.0, 2.0, 1.0);
+        let child = Transform::new(Position::new(1.0, 2.0, 0.0, 1.0), Quat::from_axis_angle(Vec3::Z.into(), 0.4), Normal::new(1.0, 1.0, 1.0));
+        let parent = Transform::new(Position::new(-3.0, 0.0, 5.0, 1.0), Quat::from_axis_angle(Vec3::Y.into(), 1.1), Normal::new(2.0, 2.0, 2.0));
+
+        let local = |transform: &Transform| Mat4::translation(-origin) * transform.matrix() * Mat4::translation(origin);
+        let world = local(&child) * local(&parent);
+        let detached = Object::transform_for(origin, world);
+
+        for position in [Position::new(0.0, 0.0, 0.0, 1.0), Position::new(4.0, -1.0, 2.5, 1.0)] {
+            assert!(close(position * local(&detached), position * world));
+        }
+    }
+}
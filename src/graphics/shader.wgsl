@@ -1,26 +1,143 @@
 // Vertex Shader
 
+struct CameraUniform {
+    view: mat4x4<f32>,
+    projection: mat4x4<f32>,
+    view_projection: mat4x4<f32>,
+    position: vec4<f32>,
+}
+
+@group(0) @binding(0)
+var<uniform> camera: CameraUniform;
+
+struct DirectionalLight {
+    direction: vec4<f32>,
+    color: vec4<f32>,
+}
+
+struct PointLight {
+    position: vec4<f32>,
+    color: vec4<f32>,
+    attenuation: vec4<f32>,
+}
+
+struct Lights {
+    ambient: vec4<f32>,
+    directional: DirectionalLight,
+    points: array<PointLight, 4>,
+    material: vec4<f32>,
+    count: vec4<u32>,
+}
+
+@group(1) @binding(0)
+var<uniform> lights: Lights;
+
+struct ModelUniform {
+    model: mat4x4<f32>,
+    normal: mat4x4<f32>,
+}
+
+@group(2) @binding(0)
+var<uniform> object: ModelUniform;
+
 struct VertexIn {
     @location(0) position: vec4<f32>,
     @location(1) color: vec4<f32>,
+    @location(2) normal: vec3<f32>,
 }
 
 struct VertexOut{
     @builtin(position) clip_position: vec4<f32>,
     @location(0) color: vec4<f32>,
+    @location(1) world_position: vec3<f32>,
+    @location(2) normal: vec3<f32>,
 }
 
 @vertex
 fn vertex_main(model: VertexIn) -> VertexOut {
     var out: VertexOut;
     out.color = model.color;
-    out.clip_position = model.position;
+    let world_position = object.model * model.position;
+    out.world_position = world_position.xyz;
+    out.normal = (object.normal * vec4<f32>(model.normal, 0.0)).xyz;
+    out.clip_position = camera.view_projection * world_position;
+    return out;
+}
+
+struct InstanceIn {
+    @location(3) model_0: vec4<f32>,
+    @location(4) model_1: vec4<f32>,
+    @location(5) model_2: vec4<f32>,
+    @location(6) model_3: vec4<f32>,
+    @location(7) normal_0: vec4<f32>,
+    @location(8) normal_1: vec4<f32>,
+    @location(9) normal_2: vec4<f32>,
+    @location(10) tint: vec4<f32>,
+}
+
+// instanced meshes carry their own transform and tint
+@vertex
+fn vertex_instanced(model: VertexIn, instance: InstanceIn) -> VertexOut {
+    let transform = mat4x4<f32>(instance.model_0, instance.model_1, instance.model_2, instance.model_3);
+    let normal = mat3x3<f32>(instance.normal_0.xyz, instance.normal_1.xyz, instance.normal_2.xyz);
+
+    var out: VertexOut;
+    out.color = model.color * instance.tint;
+    let world_position = transform * model.position;
+    out.world_position = world_position.xyz;
+    out.normal = normal * model.normal;
+    out.clip_position = camera.view_projection * world_position;
     return out;
 }
 
 // Fragment Shader
 
+// blinn-phong diffuse and specular contribution of a single light
+fn blinn_phong(normal: vec3<f32>, view_dir: vec3<f32>, light_dir: vec3<f32>, light_color: vec3<f32>, base_color: vec3<f32>) -> vec3<f32> {
+    let diffuse = max(dot(normal, light_dir), 0.0);
+
+    let half_dir = normalize(view_dir + light_dir);
+    let specular = pow(max(dot(normal, half_dir), 0.0), lights.material.y) * lights.material.x;
+
+    // no highlight on surfaces facing away from the light
+    let lit = select(0.0, 1.0, diffuse > 0.0);
+
+    return light_color * (base_color * diffuse + vec3<f32>(specular * lit));
+}
+
 @fragment
 fn fragment_main(in: VertexOut) -> @location(0) vec4<f32> {
-    return in.color;
-}
\ No newline at end of file
+    // unlit or missing normals pass the vertex color straight through
+    if (lights.material.z == 0.0 || dot(in.normal, in.normal) == 0.0) {
+        return in.color;
+    }
+
+    let normal = normalize(in.normal);
+    let view_dir = normalize(camera.position.xyz - in.world_position);
+    let base_color = in.color.rgb;
+
+    // ambient
+    var color = base_color * lights.ambient.rgb * lights.ambient.a;
+
+    // directional
+    let sun_dir = normalize(-lights.directional.direction.xyz);
+    color += blinn_phong(normal, view_dir, sun_dir, lights.directional.color.rgb * lights.directional.color.a, base_color);
+
+    // point lights fade out quadratically up to their range
+    for (var i = 0u; i < min(lights.count.x, 4u); i += 1u) {
+        let light = lights.points[i];
+        let to_light = light.position.xyz - in.world_position;
+        let distance = length(to_light);
+        let falloff = clamp(1.0 - distance / max(light.attenuation.x, 0.0001), 0.0, 1.0);
+
+        color += blinn_phong(normal, view_dir, to_light / max(distance, 0.0001), light.color.rgb * light.color.a, base_color) * falloff * falloff;
+    }
+
+    return vec4<f32>(color, in.color.a);
+}
+
+// flat color for the wireframe overlay, matching WIREFRAME_COLOR
+@fragment
+fn fragment_wireframe(in: VertexOut) -> @location(0) vec4<f32> {
+    return vec4<f32>(1.0, 1.0, 1.0, 1.0);
+}
@@ -0,0 +1,113 @@
+use crate::graphics::{Position, Normal, Mat4, Quat};
+
+// placement of an object relative to its parent, applied as scale, then rotation, then translation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Position,
+    pub rotation: Quat,
+    pub scale: Normal,
+}
+
+impl Transform {
+    pub const IDENTITY: Transform = Transform {
+        translation: Position { x: 0.0, y: 0.0, z: 0.0, w: 1.0 },
+        rotation: Quat::IDENTITY,
+        scale: Normal::new_const(1.0, 1.0, 1.0),
+    };
+
+    pub fn new(translation: Position, rotation: Quat, scale: Normal) -> Self {
+        Self { translation, rotation, scale }
+    }
+
+    pub fn from_translation(translation: Position) -> Self {
+        Self { translation, ..Self::IDENTITY }
+    }
+
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self { rotation, ..Self::IDENTITY }
+    }
+
+    // split a matrix without shear back into its scale, rotation and translation
+    pub fn from_matrix(matrix: Mat4) -> Self {
+        let rows = [0, 1, 2].map(|i| Normal::new(matrix[i][0], matrix[i][1], matrix[i][2]));
+        let mut scale = rows.map(Normal::length);
+
+        // a mirrored matrix keeps its rotation by flipping one axis
+        if rows[0].cross(rows[1]).dot(rows[2]) < 0.0 {
+            scale[0] = -scale[0];
+        }
+
+        let mut rotation = Mat4::IDENTITY;
+        for (i, row) in rows.iter().enumerate() {
+            let row = if scale[i] == 0.0 { *row } else { *row / scale[i] };
+            rotation[i] = [row[0], row[1], row[2], 0.0];
+        }
+
+        Self::new(matrix.get_translation(), Quat::from_mat4(&rotation), Normal::new(scale[0], scale[1], scale[2]))
+    }
+
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+
+    pub fn translate(&mut self, offset: Position) {
+        self.translation = Position::new(
+            self.translation.x + offset.x,
+            self.translation.y + offset.y,
+            self.translation.z + offset.z,
+            1.0,
+        );
+    }
+
+    // rotate by rotation after the current rotation, around the parent's axes
+    pub fn rotate(&mut self, rotation: Quat) {
+        self.rotation = (self.rotation * rotation).normalize();
+    }
+
+    pub fn set_scale(&mut self, scale: f32) {
+        self.scale = Normal::new(scale, scale, scale);
+    }
+
+    pub fn transform_point(&self, point: Position) -> Position {
+        self.matrix().transform_point(point)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matrix_round_trips() {
+        let transform = Transform::new(
+            Position::new(1.0, -2.0, 3.0, 1.0),
+            Quat::from_euler(0.4, 1.0, -0.3),
+            Normal::new(2.0, 0.5, 1.5),
+        );
+        let recovered = Transform::from_matrix(transform.matrix());
+
+        assert_eq!(recovered.translation, transform.translation);
+        assert!((recovered.scale - transform.scale).length() < 1e-5);
+        assert!(recovered.rotation.dot(transform.rotation).abs() > 1.0 - 1e-5);
+    }
+
+    #[test]
+    fn scales_before_rotating_and_moving() {
+        let mut transform = Transform::from_translation(Position::new(0.0, 0.0, 5.0, 1.0));
+        transform.rotate(Quat::from_axis_angle(Normal::new(0.0, 0.0, 1.0), std::f32::consts::FRAC_PI_2));
+        transform.set_scale(2.0);
+
+        // x is doubled, turned onto y and then moved along z
+        let point = transform.transform_point(Position::new(1.0, 0.0, 0.0, 1.0));
+
+        assert!(point.x.abs() < 1e-5);
+        assert!((point.y - 2.0).abs() < 1e-5);
+        assert!((point.z - 5.0).abs() < 1e-5);
+    }
+}
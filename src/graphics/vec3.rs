@@ -0,0 +1,183 @@
+use crate::graphics::position::Position;
+use crate::graphics::normal::Normal;
+
+// a direction or offset in space, unlike a position it has no w and is never translated
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, bytemuck::Pod, bytemuck::Zeroable)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Vec3 {
+    pub const ZERO: Vec3 = Vec3 { x: 0.0, y: 0.0, z: 0.0 };
+    pub const X: Vec3 = Vec3 { x: 1.0, y: 0.0, z: 0.0 };
+    pub const Y: Vec3 = Vec3 { x: 0.0, y: 1.0, z: 0.0 };
+    pub const Z: Vec3 = Vec3 { x: 0.0, y: 0.0, z: 1.0 };
+
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn dot(self, target: Vec3) -> f32 {
+        self.x * target.x + self.y * target.y + self.z * target.z
+    }
+
+    // perpendicular to both, counter-clockwise from self to target when looking down the result
+    pub fn cross(self, target: Vec3) -> Vec3 {
+        Vec3::new(
+            self.y * target.z - self.z * target.y,
+            self.z * target.x - self.x * target.z,
+            self.x * target.y - self.y * target.x,
+        )
+    }
+
+    pub fn length_squared(self) -> f32 {
+        self.dot(self)
+    }
+
+    pub fn length(self) -> f32 {
+        self.length_squared().sqrt()
+    }
+
+    // scale to unit length, leaving a zero vector untouched
+    pub fn normalize(self) -> Vec3 {
+        let length = self.length();
+
+        if length == 0.0 {
+            return self;
+        }
+
+        self / length
+    }
+
+    // the point this offset reaches from the origin
+    pub fn to_point(self) -> Position {
+        Position::new(self.x, self.y, self.z, 1.0)
+    }
+
+    // interpolate
+    pub fn interpolate(self, target: Vec3, t: f32) -> Vec3 {
+        self * (1.0 - t) + target * t
+    }
+}
+
+// implement indexing into vec3
+impl std::ops::Index<usize> for Vec3 {
+    type Output = f32;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Index out of bounds"),
+        }
+    }
+}
+
+// implement vec3 from array
+impl From<[f32; 3]> for Vec3 {
+    fn from(array: [f32; 3]) -> Self {
+        Self::new(array[0], array[1], array[2])
+    }
+}
+
+impl From<Vec3> for [f32; 3] {
+    fn from(vector: Vec3) -> Self {
+        [vector.x, vector.y, vector.z]
+    }
+}
+
+impl From<Normal> for Vec3 {
+    fn from(normal: Normal) -> Self {
+        Self::new(normal[0], normal[1], normal[2])
+    }
+}
+
+impl From<Vec3> for Normal {
+    fn from(vector: Vec3) -> Self {
+        Normal::new(vector.x, vector.y, vector.z)
+    }
+}
+
+// the x, y and z of a position, dropping w
+impl From<Position> for Vec3 {
+    fn from(position: Position) -> Self {
+        Self::new(position.x, position.y, position.z)
+    }
+}
+
+// a direction in homogeneous coordinates has a w of zero
+impl From<Vec3> for Position {
+    fn from(vector: Vec3) -> Self {
+        Position::new(vector.x, vector.y, vector.z, 0.0)
+    }
+}
+
+// arithmetic operators
+impl std::ops::Add for Vec3 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl std::ops::Sub for Vec3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl std::ops::Mul<f32> for Vec3 {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        Self::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl std::ops::Div<f32> for Vec3 {
+    type Output = Self;
+
+    fn div(self, rhs: f32) -> Self::Output {
+        Self::new(self.x / rhs, self.y / rhs, self.z / rhs)
+    }
+}
+
+impl std::ops::Neg for Vec3 {
+    type Output = Self;
+
+    fn neg(self) -> Self::Output {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+impl std::ops::AddAssign for Vec3 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl std::ops::SubAssign for Vec3 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl PartialEq for Vec3 {
+    fn eq(&self, other: &Self) -> bool {
+        self.x == other.x && self.y == other.y && self.z == other.z
+    }
+}
+
+// implement format
+impl std::fmt::Display for Vec3 {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}
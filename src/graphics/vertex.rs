@@ -36,7 +36,7 @@ impl Vertex {
 
     // calculate normal based on corners
     pub fn normal(self, a: Vertex, b: Vertex) -> Normal {
-        (a.position - self.position).cross(b.position - self.position).into()
+        Normal::from((a.position - self.position).cross(b.position - self.position)).normalize()
     }
 
     // interpolate
@@ -63,6 +63,11 @@ impl Vertex {
                     shader_location: 1,
                     format: wgpu::VertexFormat::Float32x4
                 },
+                wgpu::VertexAttribute {
+                    offset: std::mem::size_of::<[f32; 8]>() as wgpu::BufferAddress,
+                    shader_location: 2,
+                    format: wgpu::VertexFormat::Float32x3
+                },
             ]
         }
     }
@@ -1,3 +1,7 @@
 mod world;
+mod world_position;
+mod floating_origin;
 
-pub use self::world::*;
\ No newline at end of file
+pub use self::world::*;
+pub use self::world_position::*;
+pub use self::floating_origin::*;
@@ -0,0 +1,112 @@
+use crate::graphics::{Position, Vec3};
+use crate::types::WorldPosition;
+
+// how far the camera can stray from the render origin before it is moved, well inside f32's millimetre range
+pub const REBASE_DISTANCE: f32 = 1024.0;
+
+// the world position render space is centred on, kept near the camera so everything drawn stays small enough for f32
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FloatingOrigin {
+    pub origin: WorldPosition,
+    pub rebase_distance: f32,
+}
+
+impl FloatingOrigin {
+    pub fn new(origin: WorldPosition, rebase_distance: f32) -> Self {
+        Self { origin, rebase_distance }
+    }
+
+    // where a world position is drawn
+    pub fn to_render(&self, position: WorldPosition) -> Position {
+        position.relative_to(self.origin)
+    }
+
+    // where in the world a render space position is
+    pub fn to_world(&self, position: Position) -> WorldPosition {
+        WorldPosition::new(
+            self.origin.x + position.x as f64,
+            self.origin.y + position.y as f64,
+            self.origin.z + position.z as f64,
+        )
+    }
+
+    // a render space position placed under another origin, moved under this one through f64 so the jump loses nothing
+    pub fn carry(&self, position: Position, from: &FloatingOrigin) -> Position {
+        self.to_render(from.to_world(position))
+    }
+
+    // whether the focus has strayed far enough from the origin to lose precision
+    pub fn needs_rebase(&self, focus: WorldPosition) -> bool {
+        self.origin.distance(focus) > self.rebase_distance as f64
+    }
+
+    // move the origin, returning how far render space shifted
+    pub fn rebase(&mut self, origin: WorldPosition) -> Vec3 {
+        let shift = self.origin.offset_to(origin);
+        self.origin = origin;
+
+        shift
+    }
+}
+
+impl Default for FloatingOrigin {
+    fn default() -> Self {
+        Self::new(WorldPosition::ORIGIN, REBASE_DISTANCE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_precision_far_from_the_world_origin() {
+        // a hundred million kilometres out, two points a centimetre apart
+        let far = WorldPosition::new(1.0e11, -3.0e10, 7.5e10);
+        let near = far + Vec3::new(0.01, 0.0, 0.0);
+
+        // straight to f32 the centimetre is lost entirely
+        assert_eq!(near.relative_to(WorldPosition::ORIGIN), far.relative_to(WorldPosition::ORIGIN));
+
+        let mut origin = FloatingOrigin::default();
+        assert!(origin.needs_rebase(far));
+        origin.rebase(far);
+
+        let rendered = origin.to_render(near);
+        assert!((rendered.x - 0.01).abs() < 1e-4);
+        assert_eq!(origin.to_world(origin.to_render(near)), near);
+    }
+
+    #[test]
+    fn rebase_shift_keeps_render_positions_in_step() {
+        let mut origin = FloatingOrigin::default();
+        let object = WorldPosition::new(1500.0, 20.0, -40.0);
+        let before = origin.to_render(object);
+
+        let camera = WorldPosition::new(1200.0, 0.0, 0.0);
+        assert!(origin.needs_rebase(camera));
+        let shift = origin.rebase(camera);
+
+        // moving what was already in render space back by the shift lands it where the new origin draws it
+        assert_eq!(before - shift, origin.to_render(object));
+        assert!(!origin.needs_rebase(camera + Vec3::new(REBASE_DISTANCE - 1.0, 0.0, 0.0)));
+    }
+
+    #[test]
+    fn carrying_back_and_forth_does_not_drift() {
+        let near = WorldPosition::new(1.496e11, 0.0, 0.0);
+        let point = near + Vec3::new(3.25, -1.5, 8.0);
+        let mut origin = FloatingOrigin::new(near, REBASE_DISTANCE);
+        let mut position = origin.to_render(point);
+
+        // hopping between two origins far from the world origin always lands back on the same spot
+        for i in 0..1000 {
+            let previous = origin;
+            origin.rebase(near + Vec3::new(if i % 2 == 0 { 900.0 } else { 0.0 }, 0.0, 0.0));
+            position = origin.carry(position, &previous);
+        }
+
+        assert_eq!(origin.origin, near);
+        assert!(origin.to_world(position).distance(point) < 1e-3);
+    }
+}
@@ -1,107 +1,317 @@
+use crate::graphics::{Position, Vec3, Aabb};
 
+// a point in the play volume
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Location {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
 
+// what happens to something that leaves the play volume
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Boundary {
+    // come back in through the opposite side, keeping how far past the edge it went
+    #[default]
+    Wrap,
+    // stop at the edge, losing the velocity that carried it out
+    Clamp,
+    // bounce off the edge as far as it went past it, turning the velocity around
+    Reflect,
+}
 
-// struct Location {
-//     x: f32,
-//     y: f32,
-//     z: f32,
-// }
-
-// struct Bounds {
-//     min_x: f32,
-//     max_x: f32,
-//     min_y: f32,
-//     max_y: f32,
-//     min_z: f32,
-//     max_z: f32,
-// }
-
-// impl Bounds {
-//     fn new(min_x: f32, max_x: f32, min_y: f32, max_y: f32, min_z: f32, max_z: f32) -> Self {
-//         Self {
-//             min_x,
-//             max_x,
-//             min_y,
-//             max_y,
-//             min_z,
-//             max_z,
-//         }
-//     }
-
-//     fn contains(&self, location: &Location) -> bool {
-//         location.x >= self.min_x
-//             && location.x <= self.max_x
-//             && location.y >= self.min_y
-//             && location.y <= self.max_y
-//             && location.z >= self.min_z
-//             && location.z <= self.max_z
-//     }
-
-//     fn wrap_location(&self, location: &Location) -> Location {
-//         let mut x = location.x;
-//         let mut y = location.y;
-//         let mut z = location.z;
-
-//         if x < self.min_x {
-//             x = self.max_x;
-//         } else if x > self.max_x {
-//             x = self.min_x;
-//         }
-        
-//         if y < self.min_y {
-//             y = self.max_y;
-//         } else if y > self.max_y {
-//             y = self.min_y;
-//         }
-
-//         if z < self.min_z {
-//             z = self.max_z;
-//         } else if z > self.max_z {
-//             z = self.min_z;
-//         }
-
-//         Location { x, y, z }
-//     }
-// }
-
-// impl Location {
-//     fn new(x: f32, y: f32, z: f32) -> Self {
-//         Self { x, y, z }
-//     }
-
-//     fn distance(&self, other: &Self) -> f32 {
-//         let x = self.x - other.x;
-//         let y = self.y - other.y;
-//         let z = self.z - other.z;
-//         (x * x + y * y + z * z).sqrt()
-//     }
-
-//     fn direction(&self, other: &Self) -> Self {
-//         let distance = self.distance(other);
-//         Self {
-//             x: (other.x - self.x) / distance,
-//             y: (other.y - self.y) / distance,
-//             z: (other.z - self.z) / distance,
-//         }
-//     }
-
-//     fn update_location(&mut self, bounds: &Bounds){
-//         if self.x < bounds.min_x {
-//             self.x = bounds.max_x;
-//         } else if self.x > bounds.max_x {
-//             self.x = bounds.min_x;
-//         }
-        
-//         if self.y < bounds.min_y {
-//             self.y = bounds.max_y;
-//         } else if self.y > bounds.max_y {
-//             self.y = bounds.min_y;
-//         }
-
-//         if self.z < bounds.min_z {
-//             self.z = bounds.max_z;
-//         } else if self.z > bounds.max_z {
-//             self.z = bounds.min_z;
-//         }
-//     }
-// }
+impl Boundary {
+    // bring one coordinate back between min and max, with the factor its velocity along that axis is scaled by
+    fn confine(self, value: f32, min: f32, max: f32) -> (f32, f32) {
+        if value >= min && value <= max {
+            return (value, 1.0);
+        }
+
+        let size = max - min;
+        let (value, factor) = match self {
+            Boundary::Clamp => (value, 0.0),
+            Boundary::Wrap if size > 0.0 => (min + (value - min).rem_euclid(size), 1.0),
+            Boundary::Reflect if size > 0.0 => {
+                // every time it crosses the volume it bounces once, an odd number of bounces leaves it heading back
+                let crossings = ((value - min) / size).floor();
+                let past = (value - min) - crossings * size;
+
+                if crossings as i64 % 2 != 0 { (max - past, -1.0) } else { (min + past, 1.0) }
+            }
+            // a flat axis has nowhere to wrap or bounce to
+            Boundary::Wrap | Boundary::Reflect => (min, 0.0),
+        };
+
+        // rounding can leave it a hair outside
+        (value.clamp(min, max), factor)
+    }
+}
+
+// axis aligned play volume and what happens to things that leave it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min_x: f32,
+    pub max_x: f32,
+    pub min_y: f32,
+    pub max_y: f32,
+    pub min_z: f32,
+    pub max_z: f32,
+    pub boundary: Boundary,
+}
+
+impl Bounds {
+    pub fn new(min_x: f32, max_x: f32, min_y: f32, max_y: f32, min_z: f32, max_z: f32) -> Self {
+        Self {
+            min_x,
+            max_x,
+            min_y,
+            max_y,
+            min_z,
+            max_z,
+            boundary: Boundary::Wrap,
+        }
+    }
+
+    // volume between two opposite corners given in any order
+    pub fn from_corners(a: Position, b: Position) -> Self {
+        Self::new(a.x.min(b.x), a.x.max(b.x), a.y.min(b.y), a.y.max(b.y), a.z.min(b.z), a.z.max(b.z))
+    }
+
+    pub fn with_boundary(self, boundary: Boundary) -> Self {
+        Self { boundary, ..self }
+    }
+
+    pub fn min(&self) -> Location {
+        Location::new(self.min_x, self.min_y, self.min_z)
+    }
+
+    pub fn max(&self) -> Location {
+        Location::new(self.max_x, self.max_y, self.max_z)
+    }
+
+    pub fn center(&self) -> Location {
+        Location::new(
+            (self.min_x + self.max_x) / 2.0,
+            (self.min_y + self.max_y) / 2.0,
+            (self.min_z + self.max_z) / 2.0,
+        )
+    }
+
+    pub fn size(&self) -> Vec3 {
+        Vec3::new(self.max_x - self.min_x, self.max_y - self.min_y, self.max_z - self.min_z)
+    }
+
+    pub fn contains(&self, location: &Location) -> bool {
+        location.x >= self.min_x
+            && location.x <= self.max_x
+            && location.y >= self.min_y
+            && location.y <= self.max_y
+            && location.z >= self.min_z
+            && location.z <= self.max_z
+    }
+
+    pub fn contains_position(&self, position: Position) -> bool {
+        self.contains(&position.into())
+    }
+
+    // whether another volume lies wholly inside this one
+    pub fn contains_bounds(&self, other: &Bounds) -> bool {
+        self.contains(&other.min()) && self.contains(&other.max())
+    }
+
+    // whether the two volumes overlap, touching counts
+    pub fn intersects(&self, other: &Bounds) -> bool {
+        self.min_x <= other.max_x && self.max_x >= other.min_x
+            && self.min_y <= other.max_y && self.max_y >= other.min_y
+            && self.min_z <= other.max_z && self.max_z >= other.min_z
+    }
+
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.intersects(&Bounds::from(*aabb))
+    }
+
+    // the point inside the volume nearest to a location
+    pub fn closest_location(&self, location: &Location) -> Location {
+        Location::new(
+            location.x.clamp(self.min_x, self.max_x),
+            location.y.clamp(self.min_y, self.max_y),
+            location.z.clamp(self.min_z, self.max_z),
+        )
+    }
+
+    // how far a location is outside the volume, zero inside it
+    pub fn distance_to(&self, location: &Location) -> f32 {
+        location.distance(&self.closest_location(location))
+    }
+
+    pub fn to_aabb(&self) -> Aabb {
+        Aabb::new(self.min().to_position(), self.max().to_position())
+    }
+
+    // where a location ends up once the boundary has been applied
+    pub fn apply(&self, location: &Location) -> Location {
+        self.apply_with_velocity(location, Vec3::ZERO).0
+    }
+
+    // where a moving location ends up once the boundary has been applied, and the velocity it leaves with
+    pub fn apply_with_velocity(&self, location: &Location, velocity: Vec3) -> (Location, Vec3) {
+        let (x, vx) = self.boundary.confine(location.x, self.min_x, self.max_x);
+        let (y, vy) = self.boundary.confine(location.y, self.min_y, self.max_y);
+        let (z, vz) = self.boundary.confine(location.z, self.min_z, self.max_z);
+
+        (Location::new(x, y, z), Vec3::new(velocity.x * vx, velocity.y * vy, velocity.z * vz))
+    }
+
+    // keep an entity's position and velocity inside the volume, leaving w alone
+    pub fn confine(&self, position: Position, velocity: Vec3) -> (Position, Vec3) {
+        let (location, velocity) = self.apply_with_velocity(&position.into(), velocity);
+
+        (Position::new(location.x, location.y, location.z, position.w), velocity)
+    }
+
+    pub fn wrap_location(&self, location: &Location) -> Location {
+        self.with_boundary(Boundary::Wrap).apply(location)
+    }
+
+    pub fn clamp_location(&self, location: &Location) -> Location {
+        self.with_boundary(Boundary::Clamp).apply(location)
+    }
+
+    pub fn reflect_location(&self, location: &Location) -> Location {
+        self.with_boundary(Boundary::Reflect).apply(location)
+    }
+}
+
+impl From<Aabb> for Bounds {
+    fn from(aabb: Aabb) -> Self {
+        Self::from_corners(aabb.min, aabb.max)
+    }
+}
+
+impl Location {
+    pub fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn distance(&self, other: &Self) -> f32 {
+        self.offset_to(other).length()
+    }
+
+    pub fn offset_to(&self, other: &Self) -> Vec3 {
+        Vec3::new(other.x - self.x, other.y - self.y, other.z - self.z)
+    }
+
+    // unit direction towards another location, zero when they are the same
+    pub fn direction(&self, other: &Self) -> Vec3 {
+        self.offset_to(other).normalize()
+    }
+
+    // bring the location back inside the bounds using their boundary
+    pub fn update_location(&mut self, bounds: &Bounds) {
+        *self = bounds.apply(self);
+    }
+
+    pub fn to_position(self) -> Position {
+        Position::new(self.x, self.y, self.z, 1.0)
+    }
+}
+
+impl From<Position> for Location {
+    fn from(position: Position) -> Self {
+        Self::new(position.x, position.y, position.z)
+    }
+}
+
+impl From<Location> for Position {
+    fn from(location: Location) -> Self {
+        location.to_position()
+    }
+}
+
+// move a location by an offset
+impl std::ops::Add<Vec3> for Location {
+    type Output = Self;
+
+    fn add(self, rhs: Vec3) -> Self::Output {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl std::ops::AddAssign<Vec3> for Location {
+    fn add_assign(&mut self, rhs: Vec3) {
+        *self = *self + rhs;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng, rngs::StdRng};
+
+    fn cube() -> Bounds {
+        Bounds::new(-10.0, 10.0, 0.0, 5.0, -1.0, 1.0)
+    }
+
+    #[test]
+    fn each_boundary_brings_a_location_back() {
+        let outside = Location::new(12.0, -1.0, 0.5);
+
+        // wrapping keeps the overshoot, so moving off one side comes in the same distance from the other
+        assert_eq!(cube().wrap_location(&outside), Location::new(-8.0, 4.0, 0.5));
+        assert_eq!(cube().clamp_location(&outside), Location::new(10.0, 0.0, 0.5));
+        assert_eq!(cube().reflect_location(&outside), Location::new(8.0, 1.0, 0.5));
+
+        let mut location = outside;
+        location.update_location(&cube().with_boundary(Boundary::Clamp));
+        assert_eq!(location, Location::new(10.0, 0.0, 0.5));
+    }
+
+    #[test]
+    fn velocity_follows_the_boundary() {
+        let position = Position::new(11.0, 2.0, 0.0, 1.0);
+        let velocity = Vec3::new(3.0, 1.0, -2.0);
+
+        let (bounced, reflected) = cube().with_boundary(Boundary::Reflect).confine(position, velocity);
+        assert_eq!(bounced, Position::new(9.0, 2.0, 0.0, 1.0));
+        assert_eq!(reflected, Vec3::new(-3.0, 1.0, -2.0));
+
+        let (_, stopped) = cube().with_boundary(Boundary::Clamp).confine(position, velocity);
+        assert_eq!(stopped, Vec3::new(0.0, 1.0, -2.0));
+
+        let (_, kept) = cube().confine(position, velocity);
+        assert_eq!(kept, velocity);
+    }
+
+    #[test]
+    fn volume_queries() {
+        let bounds = cube();
+
+        assert!(bounds.contains_position(Position::new(10.0, 5.0, -1.0, 1.0)));
+        assert!(!bounds.contains(&Location::new(0.0, 5.5, 0.0)));
+        assert!(bounds.intersects(&Bounds::new(9.0, 20.0, 4.0, 8.0, 0.0, 0.5)));
+        assert!(!bounds.intersects(&Bounds::new(9.0, 20.0, 6.0, 8.0, 0.0, 0.5)));
+        assert!(bounds.contains_bounds(&Bounds::new(-1.0, 1.0, 1.0, 2.0, 0.0, 0.5)));
+        assert!(bounds.intersects_aabb(&bounds.to_aabb()));
+        assert_eq!(bounds.distance_to(&Location::new(13.0, 9.0, 0.0)), 5.0);
+        assert_eq!(bounds.center(), Location::new(0.0, 2.5, 0.0));
+    }
+
+    #[test]
+    fn every_boundary_keeps_things_inside() {
+        let mut rng = StdRng::seed_from_u64(25);
+
+        for boundary in [Boundary::Wrap, Boundary::Clamp, Boundary::Reflect] {
+            let bounds = cube().with_boundary(boundary);
+
+            for _ in 0..500 {
+                let location = Location::new(rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0), rng.gen_range(-100.0..100.0));
+                let inside = bounds.apply(&location);
+
+                assert!(bounds.contains(&inside), "{:?} left {:?} at {:?}", boundary, location, inside);
+                if bounds.contains(&location) {
+                    assert_eq!(inside, location);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use crate::graphics::{Position, Vec3};
+
+// a point anywhere in the open world, in f64 so it stays precise to a fraction of a millimetre across a whole star system
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WorldPosition {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl WorldPosition {
+    pub const ORIGIN: WorldPosition = WorldPosition { x: 0.0, y: 0.0, z: 0.0 };
+
+    pub fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    // the render space position of this point, only precise while it is near the origin
+    pub fn relative_to(self, origin: WorldPosition) -> Position {
+        Position::new((self.x - origin.x) as f32, (self.y - origin.y) as f32, (self.z - origin.z) as f32, 1.0)
+    }
+
+    // the offset from this point to the target, subtracted in f64 before narrowing
+    pub fn offset_to(self, target: WorldPosition) -> Vec3 {
+        Vec3::new((target.x - self.x) as f32, (target.y - self.y) as f32, (target.z - self.z) as f32)
+    }
+
+    pub fn distance(self, target: WorldPosition) -> f64 {
+        let (x, y, z) = (target.x - self.x, target.y - self.y, target.z - self.z);
+
+        (x * x + y * y + z * z).sqrt()
+    }
+
+    pub fn translate(&mut self, offset: Vec3) {
+        *self += offset;
+    }
+
+    // interpolate
+    pub fn interpolate(self, target: WorldPosition, amount: f64) -> WorldPosition {
+        WorldPosition::new(
+            self.x + (target.x - self.x) * amount,
+            self.y + (target.y - self.y) * amount,
+            self.z + (target.z - self.z) * amount,
+        )
+    }
+}
+
+// a render space point taken as relative to the world origin
+impl From<Position> for WorldPosition {
+    fn from(position: Position) -> Self {
+        Self::new(position.x as f64, position.y as f64, position.z as f64)
+    }
+}
+
+impl From<[f64; 3]> for WorldPosition {
+    fn from(array: [f64; 3]) -> Self {
+        Self::new(array[0], array[1], array[2])
+    }
+}
+
+// move a world position by an offset
+impl std::ops::Add<Vec3> for WorldPosition {
+    type Output = Self;
+
+    fn add(self, rhs: Vec3) -> Self::Output {
+        Self::new(self.x + rhs.x as f64, self.y + rhs.y as f64, self.z + rhs.z as f64)
+    }
+}
+
+impl std::ops::Sub<Vec3> for WorldPosition {
+    type Output = Self;
+
+    fn sub(self, rhs: Vec3) -> Self::Output {
+        Self::new(self.x - rhs.x as f64, self.y - rhs.y as f64, self.z - rhs.z as f64)
+    }
+}
+
+impl std::ops::AddAssign<Vec3> for WorldPosition {
+    fn add_assign(&mut self, rhs: Vec3) {
+        *self = *self + rhs;
+    }
+}
+
+// implement format
+impl std::fmt::Display for WorldPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "({}, {}, {})", self.x, self.y, self.z)
+    }
+}